@@ -53,6 +53,11 @@ pub enum IndexTag {
 
     RPMTAG_SHA256HEADER = RPMTAG_SIG_BASE + 17,
 
+    RPMTAG_VERITYSIGNATURES = RPMTAG_SIG_BASE + 20,
+    RPMTAG_VERITYSIGNATUREALGO = RPMTAG_SIG_BASE + 21,
+
+    RPMTAG_OPENPGP = RPMTAG_SIG_BASE + 22,
+
     RPMTAG_NAME = 1000,
 
     RPMTAG_VERSION = 1001,
@@ -355,6 +360,18 @@ pub enum IndexTag {
     RPMTAG_IDENTITY = 5095,
     RPMTAG_MODULARITYLABEL = 5096,
     RPMTAG_PAYLOADDIGESTALT = 5097,
+    RPMTAG_ARCHSUFFIX = 5098,
+    RPMTAG_SPEC = 5099,
+    RPMTAG_TRANSLATIONURL = 5100,
+    RPMTAG_UPSTREAMRELEASES = 5101,
+    RPMTAG_SOURCELICENSE = 5102,
+    RPMTAG_PREUNTRANS = 5103,
+    RPMTAG_POSTUNTRANS = 5104,
+    RPMTAG_PREUNTRANSPROG = 5105,
+    RPMTAG_POSTUNTRANSPROG = 5106,
+    RPMTAG_PREUNTRANSFLAGS = 5107,
+    RPMTAG_POSTUNTRANSFLAGS = 5108,
+    RPMTAG_SYSUSERS = 5109,
 }
 
 #[derive(
@@ -407,6 +424,10 @@ pub enum IndexSignatureTag {
     // If this tag is present, then the SIGTAG_FILESIGNATURE shall also be present.
     RPMSIGTAG_FILESIGNATURE_LENGTH = 275,
 
+    // The fs-verity signatures of the files, base64 encoded, and the algorithm of their keys.
+    RPMSIGTAG_VERITYSIGNATURES = IndexTag::RPMTAG_VERITYSIGNATURES as isize,
+    RPMSIGTAG_VERITYSIGNATUREALGO = IndexTag::RPMTAG_VERITYSIGNATUREALGO as isize,
+
     // This  tag  specifies  the  RSA  signature  of  the  combined  Header  and  Payload  sections.
     // The data is formatted as a Version 3 Signature Packet as specified in RFC 2440: OpenPGP Message Format.
     RPMSIGTAG_PGP = 1002,
@@ -419,8 +440,18 @@ pub enum IndexSignatureTag {
     //including the Header Record, Index Records and Header store.
     RPMSIGTAG_SHA256 = IndexTag::RPMTAG_SHA256HEADER as isize,
 
-    // A silly tag for a date.
-    RPMTAG_INSTALLTIME = IndexTag::RPMTAG_INSTALLTIME as isize,
+    // The tag contains any number of OpenPGP signatures of the Header section, each base64 encoded.
+    // Introduced with rpm 6 to support multiple signatures, i.e. during key transitions.
+    RPMSIGTAG_OPENPGP = IndexTag::RPMTAG_OPENPGP as isize,
+
+    // Zero bytes reserved for signatures added later on, which replace them so
+    // the signature header can be rewritten without moving header and payload.
+    RPMSIGTAG_RESERVEDSPACE = 1008,
+}
+
+impl IndexSignatureTag {
+    #[deprecated(note = "tag 1008 of the signature header is `RPMSIGTAG_RESERVEDSPACE`")]
+    pub const RPMTAG_INSTALLTIME: Self = Self::RPMSIGTAG_RESERVEDSPACE;
 }
 
 pub trait TypeName {
@@ -441,6 +472,8 @@ impl TypeName for IndexSignatureTag {
 
 /// lead header size
 pub const LEAD_SIZE: usize = 96;
+/// size of `RPMSIGTAG_RESERVEDSPACE` in new packages, like rpm's `%__gpg_reserved_space`
+pub const RESERVED_SPACE_SIZE: usize = 4096;
 /// rpm magic as part of the lead header
 pub const RPM_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
 
@@ -471,8 +504,9 @@ pub const RPMSENSE_EQUAL: u32 = 1 << 3;
 // const RPMSENSE_TRIGGERPOSTUN: u32 = (1 << 18);
 // const RPMSENSE_MISSINGOK: u32 = (1 << 19);
 
-// // for some weird reason, centos packages have another value for rpm lib sense. We have to observe this.
-// const RPMSENSE_RPMLIB: u32 = (1 << 24); //0o100000012;
+// for some weird reason, centos packages have another value for rpm lib sense. We have to observe this.
+// 0o100000012
+pub const RPMSENSE_RPMLIB: u32 = 1 << 24;
 // const RPMSENSE_TRIGGERPREIN: u32 = (1 << 25);
 // const RPMSENSE_KEYRING: u32 = (1 << 26);
 // const RPMSENSE_CONFIG: u32 = (1 << 28);
@@ -483,7 +517,7 @@ pub const RPMFILE_DOC: i32 = 1 << 1;
 // const RPMFILE_MISSINGOK: i32 = (1 << 3);
 // const RPMFILE_NOREPLACE: i32 = (1 << 4);
 // const RPMFILE_SPECFILE: i32 = (1 << 5);
-// const RPMFILE_GHOST: i32 = (1 << 6);
+pub const RPMFILE_GHOST: i32 = 1 << 6;
 // const RPMFILE_LICENSE: i32 = (1 << 7);
 // const RPMFILE_README: i32 = (1 << 8);
 // const RPMFILE_EXCLUDE: i32 = (1 << 9);
@@ -502,3 +536,12 @@ pub const PGPHASHALGO_SHA256: i32 = 8;
 pub const PGPHASHALGO_SHA384: i32 = 9;
 pub const PGPHASHALGO_SHA512: i32 = 10;
 pub const PGPHASHALGO_SHA224: i32 = 11;
+
+// copied from rpmpgp.h
+pub const PGPPUBKEYALGO_RSA: i32 = 1;
+pub const PGPPUBKEYALGO_RSA_SIGN: i32 = 3;
+pub const PGPPUBKEYALGO_DSA: i32 = 17;
+pub const PGPPUBKEYALGO_ECDSA: i32 = 19;
+pub const PGPPUBKEYALGO_EDDSA: i32 = 22;
+pub const PGPPUBKEYALGO_ED25519: i32 = 27;
+pub const PGPPUBKEYALGO_ED448: i32 = 28;
@@ -89,6 +89,15 @@ pub enum RPMError {
         key_ref: String,
     },
 
+    #[error("unsupported public key algorithm {algorithm} - {reason}")]
+    UnsupportedPublicKeyAlgorithm {
+        algorithm: String,
+        reason: &'static str,
+    },
+
+    #[error("unsupported hash algorithm {0} - only SHA2 is accepted for new signatures")]
+    UnsupportedHashAlgorithm(String),
+
     #[error("unable to find key with key-ref: {key_ref}")]
     KeyNotFoundError { key_ref: String },
 
@@ -100,6 +109,14 @@ pub enum RPMError {
 
     #[error("invalid file mode {raw_mode} - {reason}")]
     InvalidFileMode { raw_mode: i32, reason: &'static str },
+
+    #[error("invalid payload - {0}")]
+    InvalidPayload(String),
+
+    #[error(
+        "signature header requires {required} bytes, exceeding the {available} bytes available"
+    )]
+    InsufficientReservedSpace { required: usize, available: usize },
 }
 
 impl From<nom::Err<(&[u8], nom::error::ErrorKind)>> for RPMError {
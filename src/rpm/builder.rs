@@ -13,6 +13,7 @@ use crate::sequential_cursor::SeqCursor;
 
 use super::compressor::Compressor;
 use super::headers::*;
+use super::payload::CPIO_STRIPPED_MAGIC;
 use super::Lead;
 use crate::constants::*;
 
@@ -43,6 +44,123 @@ async fn tokio_file_mode(_file: &tokio::fs::File) -> Result<u32, RPMError> {
     Ok(0)
 }
 
+/// Keeps track of the uncompressed size and digest of the cpio archive
+/// on its way into the compressor.
+struct ArchiveWriter<W: Write> {
+    inner: W,
+    written: u64,
+    hasher: sha2::Sha256,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            written: 0,
+            hasher: sha2::Sha256::default(),
+        }
+    }
+
+    /// Number of bytes written so far, including cpio headers and padding.
+    fn len(&self) -> u64 {
+        self.written
+    }
+
+    /// Hex encoded sha256 digest of everything written.
+    fn finalize_digest(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for ArchiveWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.inner.flush()
+    }
+}
+
+/// Write a single file in rpm's stripped cpio format.
+///
+/// The newc format is limited to 4 GiB per file since the size is stored as 8 hex digits.
+/// The stripped format only references the file by its index within the header,
+/// all other metadata (including the size) is taken from the header.
+fn write_stripped_cpio_entry<W: Write>(
+    out: &mut W,
+    file_index: u32,
+    content: &[u8],
+) -> Result<(), RPMError> {
+    out.write_all(CPIO_STRIPPED_MAGIC)?;
+    out.write_all(format!("{:08x}", file_index).as_bytes())?;
+    // the 14 byte header is padded to 4 bytes, the content as well
+    out.write_all(&[0u8; 2])?;
+    out.write_all(content)?;
+    let padding = (4 - content.len() % 4) % 4;
+    out.write_all(&[0u8; 4][..padding])?;
+    Ok(())
+}
+
+/// A built package lacking only its signatures, see [`RPMBuilder::build_for_signing`].
+#[cfg(feature = "signature-meta")]
+pub struct UnsignedPackage {
+    lead: Lead,
+    header_idx_tag: Header<IndexTag>,
+    /// the serialized `header_idx_tag`
+    header: Vec<u8>,
+    content: Vec<u8>,
+    archive_size: u64,
+    builder: SignatureHeaderBuilder<WithDigest>,
+}
+
+#[cfg(feature = "signature-meta")]
+impl UnsignedPackage {
+    /// The header to sign, exactly as it will be written to the package.
+    ///
+    /// It includes the digest of the payload, so a signature of it covers the whole package.
+    pub fn header_bytes(&self) -> &[u8] {
+        self.header.as_slice()
+    }
+
+    /// Assemble the package with a signature of the [`header_bytes`](Self::header_bytes).
+    ///
+    /// Like rpm 4.16 and later, no signature spanning header and payload is added.
+    /// Fails with [`RPMError::NoSignatureFound`] if `header_signature` is not a signature.
+    pub fn assemble(self, header_signature: &[u8]) -> Result<RPMPackage, RPMError> {
+        if signature::packet::parse_signature(header_signature).is_none() {
+            return Err(RPMError::NoSignatureFound);
+        }
+        Ok(self.finish_with(|builder| builder.add_header_signature(header_signature)))
+    }
+
+    fn finish(self, sig_header_only: &[u8], sig_header_and_archive: &[u8]) -> RPMPackage {
+        self.finish_with(|builder| builder.add_signature(sig_header_only, sig_header_and_archive))
+    }
+
+    fn finish_with<F>(self, add_signatures: F) -> RPMPackage
+    where
+        F: FnOnce(SignatureHeaderBuilder<WithDigest>) -> SignatureHeaderBuilder<WithSignature>,
+    {
+        let header_and_content_len = self.header.len() + self.content.len();
+        let signature_header = add_signatures(self.builder)
+            .add_payload_size(self.archive_size)
+            .build(header_and_content_len as u64);
+
+        let metadata = RPMPackageMetadata {
+            lead: self.lead,
+            signature: signature_header,
+            header: self.header_idx_tag,
+        };
+        RPMPackage {
+            metadata,
+            content: self.content,
+        }
+    }
+}
+
 /// Builder pattern for a full rpm file.
 ///
 /// Prefered method of creating a rpm file.
@@ -76,6 +194,7 @@ pub struct RPMBuilder {
     changelog_entries: Vec<String>,
     changelog_times: Vec<i32>,
     compressor: Compressor,
+    legacy_digests: bool,
 }
 
 impl RPMBuilder {
@@ -104,6 +223,7 @@ impl RPMBuilder {
             changelog_times: Vec::new(),
             compressor: Compressor::None(Vec::new()),
             directories: BTreeSet::new(),
+            legacy_digests: true,
         }
     }
 
@@ -117,6 +237,15 @@ impl RPMBuilder {
         self
     }
 
+    /// Include the legacy md5 and sha1 digests in the signature header, enabled by default.
+    ///
+    /// rpm in FIPS mode rejects those, the sha256 header digest and
+    /// the payload digest are always present.
+    pub fn legacy_digests(mut self, enabled: bool) -> Self {
+        self.legacy_digests = enabled;
+        self
+    }
+
     pub fn add_changelog_entry<E, F>(mut self, author: E, entry: F, time: i32) -> Self
     where
         E: Into<String>,
@@ -220,7 +349,7 @@ impl RPMBuilder {
         let sha_checksum = hex::encode(hash_result); // encode as string
         let entry = RPMFileEntry {
             base_name: pb.file_name().unwrap().to_string_lossy().to_string(),
-            size: content.len() as i32,
+            size: content.len() as u64,
             content: Some(content),
             flag: options.flag,
             user: options.user,
@@ -286,23 +415,18 @@ impl RPMBuilder {
     ///
     /// ignores a present key, if any
     pub fn build(self) -> Result<RPMPackage, RPMError> {
-        let (lead, header_idx_tag, content) = self.prepare_data()?;
+        let legacy_digests = self.legacy_digests;
+        let (lead, header_idx_tag, content, archive_size) = self.prepare_data()?;
 
         let mut header = Vec::with_capacity(128);
         header_idx_tag.write(&mut header)?;
         let header = header;
 
-        let (header_digest_sha1, header_and_content_digest_md5) =
-            Self::derive_hashes(header.as_slice(), content.as_slice())?;
-
         let header_and_content_len = header.len() + content.len();
 
-        let digest_header = Header::<IndexSignatureTag>::builder()
-            .add_digest(
-                header_digest_sha1.as_str(),
-                header_and_content_digest_md5.as_slice(),
-            )
-            .build(header_and_content_len as i32);
+        let digest_header = Self::digest_builder(legacy_digests, &header, &content)?
+            .add_payload_size(archive_size)
+            .build(header_and_content_len as u64);
 
         let metadata = RPMPackageMetadata {
             lead,
@@ -317,51 +441,98 @@ impl RPMBuilder {
     ///
     /// See `signature::Signing` for more details.
     #[cfg(feature = "signature-meta")]
-    pub fn build_and_sign<S>(self, signer: S) -> Result<RPMPackage, RPMError>
+    pub fn build_and_sign<S, A>(self, signer: S) -> Result<RPMPackage, RPMError>
     where
-        S: signature::Signing<crate::signature::algorithm::RSA>,
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A>,
     {
-        let (lead, header_idx_tag, content) = self.prepare_data()?;
+        let unsigned = self.build_for_signing()?;
 
-        let mut header = Vec::with_capacity(128);
-        header_idx_tag.write(&mut header)?;
-        let header = header;
+        let rsa_sig_header_only = signer.sign(unsigned.header.as_slice())?;
 
-        let (header_digest_sha1, header_and_content_digest_md5) =
-            Self::derive_hashes(header.as_slice(), content.as_slice())?;
+        let cursor = SeqCursor::new(&[unsigned.header.as_slice(), unsigned.content.as_slice()]);
+        let rsa_sig_header_and_archive = signer.sign(cursor)?;
 
-        let header_and_content_len = header.len() + content.len();
+        Ok(unsigned.finish(
+            rsa_sig_header_only.as_ref(),
+            rsa_sig_header_and_archive.as_ref(),
+        ))
+    }
 
-        let builder = Header::<IndexSignatureTag>::builder().add_digest(
-            header_digest_sha1.as_str(),
-            header_and_content_digest_md5.as_slice(),
-        );
+    /// use an external asynchronous signer to sign and build
+    ///
+    /// See `signature::AsyncSigning` for more details.
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn build_and_sign_async<S, A>(self, signer: S) -> Result<RPMPackage, RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::AsyncSigning<A>,
+    {
+        let unsigned = self.build_for_signing()?;
 
-        let signature_header = {
-            let rsa_sig_header_only = signer.sign(header.as_slice())?;
+        let rsa_sig_header_only = signer.sign(unsigned.header.as_slice()).await?;
 
-            let cursor = SeqCursor::new(&[header.as_slice(), content.as_slice()]);
-            let rsa_sig_header_and_archive = signer.sign(cursor)?;
+        let header_and_archive =
+            AsyncReadExt::chain(unsigned.header.as_slice(), unsigned.content.as_slice());
+        let rsa_sig_header_and_archive = signer.sign(header_and_archive).await?;
 
-            builder
-                .add_signature(
-                    rsa_sig_header_only.as_ref(),
-                    rsa_sig_header_and_archive.as_ref(),
-                )
-                .build(header_and_content_len as i32)
-        };
+        Ok(unsigned.finish(
+            rsa_sig_header_only.as_ref(),
+            rsa_sig_header_and_archive.as_ref(),
+        ))
+    }
 
-        let metadata = RPMPackageMetadata {
+    /// build everything but the signatures, to sign only the header
+    ///
+    /// Signing happens in two phases, the [`header_bytes`](UnsignedPackage::header_bytes) are
+    /// signed by any means, i.e. a remote signing service, and the package is
+    /// [`assemble`](UnsignedPackage::assemble)d with that signature. Unlike with
+    /// [`build_and_sign`](Self::build_and_sign), the payload is never passed to the signer.
+    #[cfg(feature = "signature-meta")]
+    pub fn build_for_signing(self) -> Result<UnsignedPackage, RPMError> {
+        let legacy_digests = self.legacy_digests;
+        let (lead, header_idx_tag, content, archive_size) = self.prepare_data()?;
+
+        let mut header = Vec::with_capacity(128);
+        header_idx_tag.write(&mut header)?;
+
+        let builder = Self::digest_builder(legacy_digests, &header, &content)?;
+
+        Ok(UnsignedPackage {
             lead,
-            signature: signature_header,
-            header: header_idx_tag,
+            header_idx_tag,
+            header,
+            content,
+            archive_size,
+            builder,
+        })
+    }
+
+    /// create a signature header builder with all digests over the prepared data
+    fn digest_builder(
+        legacy_digests: bool,
+        header: &[u8],
+        content: &[u8],
+    ) -> Result<SignatureHeaderBuilder<WithDigest>, RPMError> {
+        let (header_digest_sha1, header_and_content_digest_md5, header_digest_sha256) =
+            Self::derive_hashes(header, content)?;
+
+        let builder = Header::<IndexSignatureTag>::builder();
+        let builder = if legacy_digests {
+            builder
+                .add_digest(
+                    header_digest_sha1.as_str(),
+                    header_and_content_digest_md5.as_slice(),
+                )
+                .add_sha256_digest(header_digest_sha256.as_str())
+        } else {
+            builder.add_sha256_digest(header_digest_sha256.as_str())
         };
-        let pkg = RPMPackage { metadata, content };
-        Ok(pkg)
+        Ok(builder)
     }
 
     /// use prepared data but make sure the signatures are
-    fn derive_hashes(header: &[u8], content: &[u8]) -> Result<(String, Vec<u8>), RPMError> {
+    fn derive_hashes(header: &[u8], content: &[u8]) -> Result<(String, Vec<u8>, String), RPMError> {
         // accross header index and content (compressed or uncompressed, depends on configuration)
         let mut hasher = md5::Md5::default();
         hasher.update(&header);
@@ -374,13 +545,19 @@ impl RPMBuilder {
         let digest_sha1 = digest_sha1.digest();
         let digest_sha1 = digest_sha1.to_string();
 
-        Ok((digest_sha1, digest_md5.to_vec()))
+        let mut hasher = sha2::Sha256::default();
+        hasher.update(header);
+        let digest_sha256 = hex::encode(hasher.finalize());
+
+        Ok((digest_sha1, digest_md5.to_vec(), digest_sha256))
     }
 
     /// prepapre all rpm headers including content
     ///
+    /// Besides the header and the compressed payload, the uncompressed size of the cpio archive is returned.
+    ///
     /// @todo split this into multiple `fn`s, one per `IndexTag`-group.
-    fn prepare_data(mut self) -> Result<(Lead, Header<IndexTag>, Vec<u8>), RPMError> {
+    fn prepare_data(mut self) -> Result<(Lead, Header<IndexTag>, Vec<u8>, u64), RPMError> {
         // signature depends on header and payload. So we build these two first.
         // then the signature. Then we stitch all toghether.
         // Lead is not important. just build it here
@@ -405,7 +582,17 @@ impl RPMBuilder {
         let mut dir_indixes = Vec::new();
         let mut base_names = Vec::new();
 
-        let mut combined_file_sizes = 0;
+        let mut combined_file_sizes: u64 = 0;
+
+        // newc can not represent files beyond 4 GiB, rpm switches to 64 bit file sizes
+        // and the stripped cpio format for the whole package in that case.
+        let large_file_threshold = large_size_threshold();
+        let large_files = self
+            .files
+            .values()
+            .any(|entry| entry.size > large_file_threshold);
+
+        let mut archive = ArchiveWriter::new(&mut self.compressor);
 
         for (cpio_path, entry) in self.files.iter() {
             combined_file_sizes += entry.size;
@@ -432,20 +619,40 @@ impl RPMBuilder {
             base_names.push(entry.base_name.to_owned());
             file_verify_flags.push(-1);
             let content = entry.content.to_owned().unwrap();
-            let mut writer = cpio::newc::Builder::new(&cpio_path)
-                .mode(entry.mode.into())
-                .ino(ino_index as u32)
-                .uid(self.uid.unwrap_or(0))
-                .gid(self.gid.unwrap_or(0))
-                .write(&mut self.compressor, content.len() as u32);
-
-            writer.write_all(&content)?;
-            writer.finish()?;
+            if large_files {
+                write_stripped_cpio_entry(&mut archive, ino_index as u32 - 1, &content)?;
+            } else {
+                let mut writer = cpio::newc::Builder::new(&cpio_path)
+                    .mode(entry.mode.into())
+                    .ino(ino_index as u32)
+                    .uid(self.uid.unwrap_or(0))
+                    .gid(self.gid.unwrap_or(0))
+                    .write(&mut archive, content.len() as u32);
+
+                writer.write_all(&content)?;
+                writer.finish()?;
+            }
 
             ino_index += 1;
         }
 
+        // the trailer is a regular newc entry, even for the stripped format
+        cpio::newc::trailer(&mut archive)?;
+        let archive_size = archive.len();
+        let payload_digest_alt = archive.finalize_digest();
+
+        let possible_compression_details = self.compressor.get_details();
+        let content = self.compressor.finish_compression()?;
+
+        let mut hasher = sha2::Sha256::default();
+        hasher.update(&content);
+        let payload_digest = hex::encode(hasher.finalize());
+
         self.requires.push(Dependency::any("/bin/sh".to_string()));
+        if large_files {
+            self.requires
+                .push(Dependency::rpm_lib("rpmlib(LargeFiles)", "4.12.0-1"));
+        }
 
         self.provides
             .push(Dependency::eq(self.name.clone(), self.version.clone()));
@@ -496,6 +703,39 @@ impl RPMBuilder {
 
         let offset = 0;
 
+        let size_entry = if combined_file_sizes > large_file_threshold {
+            IndexEntry::new(
+                IndexTag::RPMTAG_LONGSIZE,
+                offset,
+                IndexData::Int64(vec![combined_file_sizes as i64]),
+            )
+        } else {
+            IndexEntry::new(
+                IndexTag::RPMTAG_SIZE,
+                offset,
+                IndexData::Int32(vec![combined_file_sizes as u32 as i32]),
+            )
+        };
+
+        let file_sizes_entry = if large_files {
+            IndexEntry::new(
+                IndexTag::RPMTAG_LONGFILESIZES,
+                offset,
+                IndexData::Int64(file_sizes.into_iter().map(|size| size as i64).collect()),
+            )
+        } else {
+            IndexEntry::new(
+                IndexTag::RPMTAG_FILESIZES,
+                offset,
+                IndexData::Int32(
+                    file_sizes
+                        .into_iter()
+                        .map(|size| size as u32 as i32)
+                        .collect(),
+                ),
+            )
+        };
+
         let mut actual_records = if self.files.is_empty() {
             // if we have an empty RPM, we have to leave out all file related index entries.
             vec![
@@ -534,11 +774,7 @@ impl RPMBuilder {
                     offset,
                     IndexData::StringTag(self.desc),
                 ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_SIZE,
-                    offset,
-                    IndexData::Int32(vec![combined_file_sizes]),
-                ),
+                size_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_LICENSE,
                     offset,
@@ -614,11 +850,7 @@ impl RPMBuilder {
                     offset,
                     IndexData::StringTag(self.desc),
                 ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_SIZE,
-                    offset,
-                    IndexData::Int32(vec![combined_file_sizes]),
-                ),
+                size_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_LICENSE,
                     offset,
@@ -646,11 +878,7 @@ impl RPMBuilder {
                     offset,
                     IndexData::StringTag("cpio".to_string()),
                 ),
-                IndexEntry::new(
-                    IndexTag::RPMTAG_FILESIZES,
-                    offset,
-                    IndexData::Int32(file_sizes),
-                ),
+                file_sizes_entry,
                 IndexEntry::new(
                     IndexTag::RPMTAG_FILEMODES,
                     offset,
@@ -749,7 +977,21 @@ impl RPMBuilder {
             ]
         };
 
-        let possible_compression_details = self.compressor.get_details();
+        actual_records.push(IndexEntry::new(
+            IndexTag::RPMTAG_PAYLOADDIGEST,
+            offset,
+            IndexData::StringArray(vec![payload_digest]),
+        ));
+        actual_records.push(IndexEntry::new(
+            IndexTag::RPMTAG_PAYLOADDIGESTALGO,
+            offset,
+            IndexData::Int32(vec![PGPHASHALGO_SHA256]),
+        ));
+        actual_records.push(IndexEntry::new(
+            IndexTag::RPMTAG_PAYLOADDIGESTALT,
+            offset,
+            IndexData::StringArray(vec![payload_digest_alt]),
+        ));
 
         if let Some(details) = possible_compression_details {
             actual_records.push(IndexEntry::new(
@@ -896,9 +1138,6 @@ impl RPMBuilder {
         //     "4.6.0-1".to_string(),
         // ));
 
-        self.compressor = cpio::newc::trailer(self.compressor)?;
-        let content = self.compressor.finish_compression()?;
-
-        Ok((lead, header, content))
+        Ok((lead, header, content, archive_size))
     }
 }
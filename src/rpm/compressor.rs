@@ -1,5 +1,5 @@
 use crate::errors::*;
-use std::io::Write;
+use std::io::{Read, Write};
 
 pub enum Compressor {
     None(Vec<u8>),
@@ -67,3 +67,22 @@ pub(crate) struct CompressionDetails {
     pub(crate) compression_level: &'static str,
     pub(crate) compression_name: &'static str,
 }
+
+/// Wrap `input` with a decompressor matching the `RPMTAG_PAYLOADCOMPRESSOR` of a package.
+pub(crate) fn decompress_stream<'a, R: Read + 'a>(
+    compressor_name: &str,
+    input: R,
+) -> Result<Box<dyn Read + 'a>, RPMError> {
+    match compressor_name {
+        "none" | "" => Ok(Box::new(input)),
+        "gzip" => Ok(Box::new(libflate::gzip::Decoder::new(input)?)),
+        "zstd" => Ok(Box::new(zstd::stream::Decoder::new(input)?)),
+        "xz" => Ok(Box::new(xz2::read::XzDecoder::new_multi_decoder(input))),
+        "lzma" => {
+            let stream =
+                xz2::stream::Stream::new_lzma_decoder(u64::MAX).map_err(std::io::Error::from)?;
+            Ok(Box::new(xz2::read::XzDecoder::new_stream(input, stream)))
+        }
+        _ => Err(RPMError::UnknownCompressorType(compressor_name.to_string())),
+    }
+}
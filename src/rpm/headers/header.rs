@@ -37,7 +37,16 @@ impl<T> Tag for T where
 pub struct Header<T: num::FromPrimitive> {
     pub(crate) index_header: IndexHeader,
     pub(crate) index_entries: Vec<IndexEntry<T>>,
+    /// entries with tags `T` lacks, i.e. ones introduced by newer rpm versions
+    ///
+    /// They are kept as is and written in between the other ones, in order of their tag.
+    pub(crate) unknown_entries: Vec<RawIndexEntry>,
     pub(crate) store: Vec<u8>,
+    /// the header as parsed, written as is as long as it is not modified
+    ///
+    /// Re-serializing a parsed header does not necessarily reproduce it byte for
+    /// byte, which would invalidate its digests and signatures.
+    pub(crate) raw: Option<Vec<u8>>,
 }
 
 impl<T> Header<T>
@@ -52,17 +61,21 @@ where
         input.read_exact(&mut buf).await?;
         let index_header = IndexHeader::parse(&buf)?;
         // read rest of header => each index consists of 16 bytes. The index header knows how large the store is.
-        let mut buf = vec![0; (index_header.header_size + index_header.num_entries * 16) as usize];
-        input.read_exact(&mut buf).await?;
-        Self::parse_header(index_header, &buf[..])
+        let mut raw =
+            vec![0; 16 + (index_header.header_size + index_header.num_entries * 16) as usize];
+        raw[..16].copy_from_slice(&buf);
+        input.read_exact(&mut raw[16..]).await?;
+        Self::parse_header(index_header, raw)
     }
 
-    fn parse_header(index_header: IndexHeader, mut bytes: &[u8]) -> Result<Header<T>, RPMError> {
+    /// Parse the entries and the store following the `index_header`, all of them within `raw`.
+    fn parse_header(index_header: IndexHeader, raw: Vec<u8>) -> Result<Header<T>, RPMError> {
+        let mut bytes = &raw[16..];
         // parse all entries
-        let mut entries: Vec<IndexEntry<T>> = Vec::new();
+        let mut entries: Vec<RawIndexEntry> = Vec::new();
         let mut buf_len = bytes.len();
         for _ in 0..index_header.num_entries {
-            let (rest, entry) = IndexEntry::parse(bytes)?;
+            let (rest, entry) = RawIndexEntry::parse::<T>(bytes)?;
             entries.push(entry);
             bytes = rest;
             assert_eq!(16, buf_len - bytes.len());
@@ -103,7 +116,8 @@ where
                     for _ in 0..entry.num_items {
                         let (rest, raw_string) = complete::take_till(|item| item == 0)(remaining)?;
                         // the null byte is still in there.. we need to cut it out.
-                        remaining = &rest[1..];
+                        let (rest, _) = complete::take(1usize)(rest)?;
+                        remaining = rest;
                         let string = String::from_utf8_lossy(raw_string).to_string();
                         strings.push(string);
                     }
@@ -111,6 +125,7 @@ where
                 IndexData::I18NString(ref mut strings) => {
                     for _ in 0..entry.num_items {
                         let (rest, raw_string) = complete::take_till(|item| item == 0)(remaining)?;
+                        let (rest, _) = complete::take(1usize)(rest)?;
                         remaining = rest;
                         let string = String::from_utf8_lossy(raw_string).to_string();
                         strings.push(string);
@@ -119,10 +134,26 @@ where
             }
         }
 
+        let mut index_entries = Vec::new();
+        let mut unknown_entries = Vec::new();
+        for entry in entries {
+            match num::FromPrimitive::from_u32(entry.tag) {
+                Some(tag) => index_entries.push(IndexEntry {
+                    tag,
+                    data: entry.data,
+                    offset: entry.offset,
+                    num_items: entry.num_items,
+                }),
+                None => unknown_entries.push(entry),
+            }
+        }
+
         Ok(Header {
             index_header,
-            index_entries: entries,
+            index_entries,
+            unknown_entries,
             store,
+            raw: Some(raw),
         })
     }
     pub(crate) fn parse<I: std::io::BufRead>(input: &mut I) -> Result<Header<T>, RPMError> {
@@ -130,9 +161,11 @@ where
         input.read_exact(&mut buf)?;
         let index_header = IndexHeader::parse(&buf)?;
         // read rest of header => each index consists of 16 bytes. The index header knows how large the store is.
-        let mut buf = vec![0; (index_header.header_size + index_header.num_entries * 16) as usize];
-        input.read_exact(&mut buf)?;
-        Self::parse_header(index_header, &buf[..])
+        let mut raw =
+            vec![0; 16 + (index_header.header_size + index_header.num_entries * 16) as usize];
+        raw[..16].copy_from_slice(&buf);
+        input.read_exact(&mut raw[16..])?;
+        Self::parse_header(index_header, raw)
     }
 
     #[cfg(feature = "async-tokio")]
@@ -140,23 +173,67 @@ where
         &self,
         out: &mut W,
     ) -> Result<(), RPMError> {
+        if let Some(raw) = &self.raw {
+            out.write_all(raw).await?;
+            return Ok(());
+        }
         self.index_header.write_async(out).await?;
-        for entry in &self.index_entries {
+        // the region entry comes first, whatever its tag
+        let (region, entries) = self.index_entries.split_at(self.index_entries.len().min(1));
+        for entry in region {
             entry.write_index_async(out).await?;
         }
+        for slot in index_order(entries, &self.unknown_entries) {
+            match slot {
+                IndexSlot::Known(i) => entries[i].write_index_async(out).await?,
+                IndexSlot::Unknown(i) => self.unknown_entries[i].write_index_async(out).await?,
+            }
+        }
         out.write_all(&self.store).await?;
         Ok(())
     }
 
     pub(crate) fn write<W: std::io::Write>(&self, out: &mut W) -> Result<(), RPMError> {
+        if let Some(raw) = &self.raw {
+            out.write_all(raw)?;
+            return Ok(());
+        }
         self.index_header.write(out)?;
-        for entry in &self.index_entries {
+        // the region entry comes first, whatever its tag
+        let (region, entries) = self.index_entries.split_at(self.index_entries.len().min(1));
+        for entry in region {
             entry.write_index(out)?;
         }
+        for slot in index_order(entries, &self.unknown_entries) {
+            match slot {
+                IndexSlot::Known(i) => entries[i].write_index(out)?,
+                IndexSlot::Unknown(i) => self.unknown_entries[i].write_index(out)?,
+            }
+        }
         out.write_all(&self.store)?;
         Ok(())
     }
 
+    /// The data of `tag`, if present.
+    ///
+    /// Gives access to all tags, including the ones without a dedicated getter.
+    pub fn get(&self, tag: T) -> Option<&IndexData> {
+        self.find_entry_or_err(&tag).ok().map(|entry| &entry.data)
+    }
+
+    /// All entries in the order of the index.
+    ///
+    /// The first one is the region entry, like `RPMTAG_HEADERIMMUTABLE`, for
+    /// headers with a region, which all headers written by rpm have.
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry<T>> {
+        self.index_entries.iter()
+    }
+
+    /// All entries with tags `T` lacks, with the raw tag.
+    pub fn unknown_entries(&self) -> impl Iterator<Item = &RawIndexEntry> {
+        self.unknown_entries.iter()
+    }
+
     pub(crate) fn find_entry_or_err(&self, tag: &T) -> Result<&IndexEntry<T>, RPMError> {
         self.index_entries
             .iter()
@@ -269,16 +346,49 @@ where
         IndexEntry::new(tag, offset, IndexData::Bin(header_immutable_index_data))
     }
 
-    pub(crate) fn from_entries(mut actual_records: Vec<IndexEntry<T>>, region_tag: T) -> Self {
+    /// Apply `modify` to all entries but the region tag and rebuild the header from them.
+    pub(crate) fn modify_entries<F>(&mut self, region_tag: T, modify: F)
+    where
+        F: FnOnce(&mut Vec<IndexEntry<T>>),
+    {
+        let mut entries = std::mem::take(&mut self.index_entries);
+        let unknown_entries = std::mem::take(&mut self.unknown_entries);
+        entries.retain(|entry| entry.tag != region_tag);
+        modify(&mut entries);
+        *self = Self::from_all_entries(entries, unknown_entries, region_tag);
+    }
+
+    pub(crate) fn from_entries(actual_records: Vec<IndexEntry<T>>, region_tag: T) -> Self {
+        Self::from_all_entries(actual_records, Vec::new(), region_tag)
+    }
+
+    /// Like [`from_entries`](Self::from_entries), with the entries `T` lacks merged in by tag.
+    fn from_all_entries(
+        mut actual_records: Vec<IndexEntry<T>>,
+        mut unknown_records: Vec<RawIndexEntry>,
+        region_tag: T,
+    ) -> Self {
         let mut store = Vec::new();
-        for record in &mut actual_records {
-            record.offset = store.len() as i32;
-            let alignment = record.data.append(&mut store);
-            record.offset += alignment as i32;
+        // the store is laid out in the order of the index
+        for slot in index_order(&actual_records, &unknown_records) {
+            let (data, offset) = match slot {
+                IndexSlot::Known(i) => {
+                    let record = &mut actual_records[i];
+                    (&record.data, &mut record.offset)
+                }
+                IndexSlot::Unknown(i) => {
+                    let record = &mut unknown_records[i];
+                    (&record.data, &mut record.offset)
+                }
+            };
+            *offset = store.len() as i32;
+            let alignment = data.append(&mut store);
+            *offset += alignment as i32;
         }
 
+        let records_count = actual_records.len() + unknown_records.len();
         let region_tag =
-            Self::create_region_tag(region_tag, actual_records.len() as i32, store.len() as i32);
+            Self::create_region_tag(region_tag, records_count as i32, store.len() as i32);
         region_tag.data.append(&mut store);
 
         let mut all_records = vec![region_tag];
@@ -290,36 +400,21 @@ where
         // if store_size % 8 > 0 {
         //     store_size += 8 - (store_size % 8);
         // }
-        let index_header = IndexHeader::new(all_records.len() as u32, store_size as u32);
+        let index_header = IndexHeader::new(
+            (all_records.len() + unknown_records.len()) as u32,
+            store_size as u32,
+        );
         Header {
             index_entries: all_records,
+            unknown_entries: unknown_records,
             index_header,
             store,
+            raw: None,
         }
     }
 }
 
 impl Header<IndexSignatureTag> {
-    /// Create a new full signature header.
-    ///
-    /// `size` is combined size of header, header store and the payload
-    ///
-    /// PGP and RSA tags expect signatures according to [RFC2440](https://tools.ietf.org/html/rfc2440)
-    ///
-    /// Please use the [`builder`](Self::builder()) which has modular and safe API.
-    pub(crate) fn new_signature_header(
-        size: i32,
-        md5sum: &[u8],
-        sha1: String,
-        rsa_spanning_header: &[u8],
-        rsa_spanning_header_and_archive: &[u8],
-    ) -> Self {
-        SignatureHeaderBuilder::new()
-            .add_digest(sha1.as_str(), md5sum)
-            .add_signature(rsa_spanning_header, rsa_spanning_header_and_archive)
-            .build(size)
-    }
-
     pub fn builder() -> SignatureHeaderBuilder<Empty> {
         SignatureHeaderBuilder::<Empty>::new()
     }
@@ -387,9 +482,270 @@ impl Header<IndexSignatureTag> {
     pub fn get_file_ima_signature_length(&self) -> Result<i32, RPMError> {
         self.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_FILESIGNATURE_LENGTH)
     }
+
+    /// The header signatures of `RPMSIGTAG_OPENPGP`, decoded to binary OpenPGP packets.
+    pub fn get_openpgp_signatures(&self) -> Result<Vec<Vec<u8>>, RPMError> {
+        self.get_entry_string_array_data(IndexSignatureTag::RPMSIGTAG_OPENPGP)?
+            .iter()
+            .map(|encoded| base64::decode(encoded).map_err(|_| RPMError::NoSignatureFound))
+            .collect()
+    }
+
+    /// Append a header signature to `RPMSIGTAG_OPENPGP`, keeping all other entries.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn add_openpgp_signature(&mut self, signature: &[u8]) {
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            push_openpgp_signature(entries, signature)
+        });
+    }
+
+    /// Whether the tags for the algorithm of the `header_only` signature are already taken.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn signature_tags_taken(&self, header_only: &[u8]) -> bool {
+        let (header_only_tag, header_and_archive_tag) = signature_tags(header_only);
+        self.index_entries
+            .iter()
+            .any(|entry| entry.tag == header_only_tag || entry.tag == header_and_archive_tag)
+    }
+
+    /// Add a header only and a header and payload signature, keeping all other entries.
+    ///
+    /// They are stored in the tags for their algorithm, like the builder does. If these
+    /// are already taken, only the header only signature is added to `RPMSIGTAG_OPENPGP`,
+    /// so callers check [`signature_tags_taken`](Self::signature_tags_taken) before
+    /// creating the header and payload signature.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn add_signature(&mut self, header_only: &[u8], header_and_archive: &[u8]) {
+        let taken = self.signature_tags_taken(header_only);
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            if taken {
+                push_openpgp_signature(entries, header_only);
+            } else {
+                push_signature(entries, header_only, header_and_archive);
+            }
+        });
+    }
+
+    /// Add a header only signature, keeping all other entries.
+    ///
+    /// Like [`add_signature`](Self::add_signature), it is stored in the tag for its
+    /// algorithm, or added to `RPMSIGTAG_OPENPGP` if that is already taken.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn add_header_signature(&mut self, header_only: &[u8]) {
+        let (header_only_tag, _) = signature_tags(header_only);
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            if entries.iter().any(|entry| entry.tag == header_only_tag) {
+                push_openpgp_signature(entries, header_only);
+            } else {
+                push_header_signature(entries, header_only);
+            }
+        });
+    }
+
+    /// The number of bytes written by `write_signature`, including the padding.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn written_len(&self) -> usize {
+        let entries = self.index_entries.len() + self.unknown_entries.len();
+        let len = 16 + 16 * entries + self.store.len();
+        len + (8 - len % 8) % 8
+    }
+
+    /// Resize `RPMSIGTAG_RESERVEDSPACE` so the written header takes exactly `len` bytes,
+    /// which is a multiple of 8 like the [`written_len`](Self::written_len) of any header.
+    ///
+    /// Fails without modifying any entry if there is no reserved space or it is too small.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn fit_reserved_space(&mut self, len: usize) -> Result<(), RPMError> {
+        let reserved = match self.get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE)
+        {
+            Ok(reserved) => reserved.len(),
+            Err(_) => {
+                return Err(RPMError::InsufficientReservedSpace {
+                    required: self.written_len(),
+                    available: len,
+                })
+            }
+        };
+        // lay out the entries as they will be written
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |_| {});
+        // both lengths are multiples of 8, so changing the reserved space by their difference
+        // neither affects the alignment of the entries after it nor the padding
+        let written = self.written_len();
+        if written - reserved >= len {
+            return Err(RPMError::InsufficientReservedSpace {
+                required: (written - reserved) / 8 * 8 + 8,
+                available: len,
+            });
+        }
+        let reserved = reserved + len - written;
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            for entry in entries.iter_mut() {
+                if entry.tag == IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE {
+                    entry.data = IndexData::Bin(vec![0u8; reserved]);
+                    entry.num_items = entry.data.num_items();
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Remove all signatures, keeping the digests and all other entries.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn remove_signatures(&mut self) {
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            entries.retain(|entry| !SIGNATURE_TAGS.contains(&entry.tag))
+        });
+    }
+
+    /// Replace the size and the digests of header and payload, keeping all other entries.
+    ///
+    /// The legacy MD5 and SHA1 digests are only replaced if present.
+    #[cfg(feature = "signature-meta")]
+    pub(crate) fn set_digests(
+        &mut self,
+        signature_size: u64,
+        digest_header_and_archive: &[u8],
+        digest_header_sha1: &str,
+        digest_header_sha256: &str,
+    ) {
+        self.modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |entries| {
+            for entry in entries.iter_mut() {
+                match entry.tag {
+                    IndexSignatureTag::RPMSIGTAG_MD5 => {
+                        entry.data = IndexData::Bin(digest_header_and_archive.to_vec())
+                    }
+                    IndexSignatureTag::RPMSIGTAG_SHA1 => {
+                        entry.data = IndexData::StringTag(digest_header_sha1.to_string())
+                    }
+                    IndexSignatureTag::RPMSIGTAG_SHA256 => {
+                        entry.data = IndexData::StringTag(digest_header_sha256.to_string())
+                    }
+                    _ => continue,
+                }
+                entry.num_items = entry.data.num_items();
+            }
+            if !entries
+                .iter()
+                .any(|entry| entry.tag == IndexSignatureTag::RPMSIGTAG_SHA256)
+            {
+                entries.push(IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_SHA256,
+                    0i32, // filled externally later on
+                    IndexData::StringTag(digest_header_sha256.to_string()),
+                ));
+            }
+
+            entries.retain(|entry| {
+                entry.tag != IndexSignatureTag::RPMSIGTAG_SIZE
+                    && entry.tag != IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE
+            });
+            entries.insert(0, size_entry(signature_size));
+        });
+    }
 }
 
 impl Header<IndexTag> {
+    /// Set `tag` to the string `value`, replacing a present entry.
+    ///
+    /// Like all modifications of the header, this invalidates the digests and
+    /// signatures of the package, so it has to be [`sign`](crate::RPMPackage::sign)ed
+    /// afterwards. A present entry of another type is not replaced but reported as
+    /// [`RPMError::UnexpectedTagDataType`], except for a translatable string like
+    /// `RPMTAG_SUMMARY`, which is replaced by the untranslated `value`.
+    pub fn set_string(&mut self, tag: IndexTag, value: &str) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::StringTag(value.to_string()))
+    }
+
+    /// Set `tag` to the string array `values`, see [`set_string`](Self::set_string).
+    pub fn set_string_array<S: AsRef<str>>(
+        &mut self,
+        tag: IndexTag,
+        values: &[S],
+    ) -> Result<(), RPMError> {
+        let values = values
+            .iter()
+            .map(|value| value.as_ref().to_string())
+            .collect();
+        self.set_entry(tag, IndexData::StringArray(values))
+    }
+
+    /// Set `tag` to the integer `value`, see [`set_string`](Self::set_string).
+    pub fn set_i32(&mut self, tag: IndexTag, value: i32) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::Int32(vec![value]))
+    }
+
+    /// Set `tag` to the integer array `values`, see [`set_string`](Self::set_string).
+    pub fn set_i32_array(&mut self, tag: IndexTag, values: &[i32]) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::Int32(values.to_vec()))
+    }
+
+    /// Set `tag` to the integer `value`, see [`set_string`](Self::set_string).
+    pub fn set_i64(&mut self, tag: IndexTag, value: i64) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::Int64(vec![value]))
+    }
+
+    /// Set `tag` to the integer array `values`, see [`set_string`](Self::set_string).
+    pub fn set_i64_array(&mut self, tag: IndexTag, values: &[i64]) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::Int64(values.to_vec()))
+    }
+
+    /// Set `tag` to the binary `value`, see [`set_string`](Self::set_string).
+    pub fn set_binary(&mut self, tag: IndexTag, value: &[u8]) -> Result<(), RPMError> {
+        self.set_entry(tag, IndexData::Bin(value.to_vec()))
+    }
+
+    /// Remove `tag`, returning whether it was present.
+    ///
+    /// The package has to be [`sign`](crate::RPMPackage::sign)ed afterwards, see
+    /// [`set_string`](Self::set_string).
+    pub fn remove(&mut self, tag: IndexTag) -> bool {
+        if tag == IndexTag::RPMTAG_HEADERIMMUTABLE || self.find_entry_or_err(&tag).is_err() {
+            return false;
+        }
+        self.modify_entries(IndexTag::RPMTAG_HEADERIMMUTABLE, |entries| {
+            entries.retain(|entry| entry.tag != tag)
+        });
+        true
+    }
+
+    /// Replace the data of a present entry in place, or insert a new one ordered by tag like rpm.
+    fn set_entry(&mut self, tag: IndexTag, mut data: IndexData) -> Result<(), RPMError> {
+        if tag == IndexTag::RPMTAG_HEADERIMMUTABLE {
+            return Err(RPMError::InvalidTag {
+                raw_tag: tag as u32,
+                store_type: IndexTag::type_name(),
+            });
+        }
+        if let Ok(entry) = self.find_entry_or_err(&tag) {
+            if let (IndexData::I18NString(_), IndexData::StringTag(value)) = (&entry.data, &data) {
+                data = IndexData::I18NString(vec![value.clone()]);
+            }
+            if entry.data.to_u32() != data.to_u32() {
+                return Err(RPMError::UnexpectedTagDataType {
+                    expected_data_type: data.type_name(),
+                    actual_data_type: entry.data.to_string(),
+                    tag: tag.to_string(),
+                });
+            }
+        }
+        self.modify_entries(IndexTag::RPMTAG_HEADERIMMUTABLE, |entries| {
+            match entries.iter_mut().find(|entry| entry.tag == tag) {
+                Some(entry) => {
+                    entry.num_items = data.num_items();
+                    entry.data = data;
+                }
+                None => {
+                    let index = entries
+                        .iter()
+                        .position(|entry| entry.tag as u32 > tag as u32)
+                        .unwrap_or(entries.len());
+                    entries.insert(index, IndexEntry::new(tag, 0, data));
+                }
+            }
+        });
+        Ok(())
+    }
+
     #[inline]
     pub fn get_payload_format(&self) -> Result<&str, RPMError> {
         self.get_entry_string_data(IndexTag::RPMTAG_PAYLOADFORMAT)
@@ -400,6 +756,49 @@ impl Header<IndexTag> {
         self.get_entry_string_data(IndexTag::RPMTAG_PAYLOADCOMPRESSOR)
     }
 
+    /// The digest of the compressed payload, hex encoded.
+    ///
+    /// Use [`get_payload_digest_algorithm`](Self::get_payload_digest_algorithm) to obtain the algorithm.
+    #[inline]
+    pub fn get_payload_digest(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_PAYLOADDIGEST)
+            .and_then(|digests| {
+                digests
+                    .first()
+                    .map(|digest| digest.as_str())
+                    .ok_or_else(|| {
+                        RPMError::TagNotFound(IndexTag::RPMTAG_PAYLOADDIGEST.to_string())
+                    })
+            })
+    }
+
+    /// The digest of the uncompressed payload, hex encoded.
+    #[inline]
+    pub fn get_payload_digest_alt(&self) -> Result<&str, RPMError> {
+        self.get_entry_string_array_data(IndexTag::RPMTAG_PAYLOADDIGESTALT)
+            .and_then(|digests| {
+                digests
+                    .first()
+                    .map(|digest| digest.as_str())
+                    .ok_or_else(|| {
+                        RPMError::TagNotFound(IndexTag::RPMTAG_PAYLOADDIGESTALT.to_string())
+                    })
+            })
+    }
+
+    /// The digest algorithm used for the payload digests.
+    pub fn get_payload_digest_algorithm(&self) -> Result<FileDigestAlgorithm, RPMError> {
+        self.get_entry_i32_data(IndexTag::RPMTAG_PAYLOADDIGESTALGO)
+            .and_then(|x| {
+                FileDigestAlgorithm::from_i32(x).ok_or_else(|| {
+                    RPMError::InvalidTagValueEnumVariant {
+                        tag: IndexTag::RPMTAG_PAYLOADDIGESTALGO.to_string(),
+                        variant: x as u32,
+                    }
+                })
+            })
+    }
+
     #[inline]
     pub fn get_file_checksums(&self) -> Result<&[String], RPMError> {
         self.get_entry_string_array_data(IndexTag::RPMTAG_FILEDIGESTS)
@@ -481,6 +880,27 @@ impl Header<IndexTag> {
             })
     }
 
+    /// The size of each file, read from `RPMTAG_LONGFILESIZES` if present.
+    pub(crate) fn get_file_sizes(&self) -> Result<Vec<u64>, RPMError> {
+        self.get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)
+            .map(|file_sizes| {
+                file_sizes
+                    .into_iter()
+                    .map(|file_size| file_size as u64)
+                    .collect()
+            })
+            .or_else(|_e| {
+                self.get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
+                    .map(|file_sizes| {
+                        // stored as uint32 by rpm
+                        file_sizes
+                            .into_iter()
+                            .map(|file_size| file_size as u32 as u64)
+                            .collect()
+                    })
+            })
+    }
+
     /// Extract a the set of contained file names including the additional metadata.
     pub fn get_file_entries(&self) -> Result<Vec<FileEntry>, RPMError> {
         // rpm does not encode it, if it is the default md5
@@ -491,17 +911,7 @@ impl Header<IndexTag> {
         let groups = self.get_entry_string_array_data(IndexTag::RPMTAG_FILEGROUPNAME)?;
         let digests = self.get_entry_string_array_data(IndexTag::RPMTAG_FILEDIGESTS)?;
         let mtimes = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILEMTIMES)?;
-        let sizes = self
-            .get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)
-            .or_else(|_e| {
-                self.get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)
-                    .map(|file_sizes| {
-                        file_sizes
-                            .into_iter()
-                            .map(|file_size| file_size as _)
-                            .collect::<Vec<i64>>()
-                    })
-            })?;
+        let sizes = self.get_file_sizes()?;
         let flags = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILEFLAGS)?;
         // @todo
         // let caps = self.get_entry_i32_array_data(IndexTag::RPMTAG_FILECAPS)?;
@@ -571,7 +981,7 @@ impl Default for FileCategory {
 }
 
 #[repr(i32)]
-#[derive(Debug, Clone, Copy, enum_primitive_derive::Primitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_primitive_derive::Primitive)]
 pub enum FileDigestAlgorithm {
     // broken and very broken
     Md5 = constants::PGPHASHALGO_MD5,
@@ -597,6 +1007,20 @@ impl Default for FileDigestAlgorithm {
     }
 }
 
+/// Public key algorithm of an OpenPGP signature.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_primitive_derive::Primitive)]
+pub enum PublicKeyAlgorithm {
+    RSA = constants::PGPPUBKEYALGO_RSA,
+    /// deprecated, treated like `RSA`
+    RSASignOnly = constants::PGPPUBKEYALGO_RSA_SIGN,
+    DSA = constants::PGPPUBKEYALGO_DSA,
+    ECDSA = constants::PGPPUBKEYALGO_ECDSA,
+    EdDSA = constants::PGPPUBKEYALGO_EDDSA,
+    Ed25519 = constants::PGPPUBKEYALGO_ED25519,
+    Ed448 = constants::PGPPUBKEYALGO_ED448,
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum FileDigest {
     Md5(Vec<u8>),
@@ -669,9 +1093,11 @@ mod tests2 {
 
     #[test]
     fn signature_header_build() {
-        let size: i32 = 209_348;
+        let size: u64 = 209_348;
         let md5sum: &[u8] = &[22u8; 16];
         let sha1: String = "5A884F0CB41EC3DA6D6E7FC2F6AB9DECA8826E8D".to_owned();
+        let sha256: String =
+            "2C26B46B68FFC68FF99B453C1D30413413422D706483BFA0F98A5E886266E7AE".to_owned();
         let rsa_spanning_header: &[u8] = b"111222333444";
         let rsa_spanning_header_and_archive: &[u8] = b"7777888899990000";
 
@@ -681,7 +1107,7 @@ mod tests2 {
                 IndexEntry::new(
                     IndexSignatureTag::RPMSIGTAG_SIZE,
                     offset,
-                    IndexData::Int32(vec![size]),
+                    IndexData::Int32(vec![size as i32]),
                 ),
                 // TODO consider dropping md5 in favour of sha256
                 IndexEntry::new(
@@ -694,6 +1120,11 @@ mod tests2 {
                     offset,
                     IndexData::StringTag(sha1.clone()),
                 ),
+                IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_SHA256,
+                    offset,
+                    IndexData::StringTag(sha256.clone()),
+                ),
                 IndexEntry::new(
                     IndexSignatureTag::RPMSIGTAG_RSA,
                     offset,
@@ -704,20 +1135,235 @@ mod tests2 {
                     offset,
                     IndexData::Bin(rsa_spanning_header_and_archive.to_vec()),
                 ),
+                IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE,
+                    offset,
+                    IndexData::Bin(vec![0u8; RESERVED_SPACE_SIZE]),
+                ),
             ];
             Header::<IndexSignatureTag>::from_entries(entries, IndexSignatureTag::HEADER_SIGNATURES)
         };
 
-        let built = Header::<IndexSignatureTag>::new_signature_header(
-            size,
-            md5sum,
-            sha1,
-            rsa_spanning_header,
-            rsa_spanning_header_and_archive,
-        );
+        let built = Header::<IndexSignatureTag>::builder()
+            .add_digest(sha1.as_str(), md5sum)
+            .add_sha256_digest(sha256.as_str())
+            .add_signature(rsa_spanning_header, rsa_spanning_header_and_archive)
+            .build(size);
 
         assert_eq!(built, truth);
     }
+
+    #[test]
+    fn set_and_remove_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let mut header = Header::<IndexTag>::from_entries(
+            vec![
+                IndexEntry::new(
+                    IndexTag::RPMTAG_NAME,
+                    0,
+                    IndexData::StringTag("name".to_string()),
+                ),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_RELEASE,
+                    0,
+                    IndexData::StringTag("1".to_string()),
+                ),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_SUMMARY,
+                    0,
+                    IndexData::I18NString(vec![
+                        "summary".to_string(),
+                        "Zusammenfassung".to_string(),
+                    ]),
+                ),
+            ],
+            IndexTag::RPMTAG_HEADERIMMUTABLE,
+        );
+
+        header.set_string(IndexTag::RPMTAG_RELEASE, "2")?;
+        header.set_string(IndexTag::RPMTAG_SUMMARY, "new summary")?;
+        header.set_string(IndexTag::RPMTAG_DISTTAG, "mdv")?;
+        header.set_i32(IndexTag::RPMTAG_EPOCH, 1)?;
+        assert!(matches!(
+            header.set_i32(IndexTag::RPMTAG_RELEASE, 3),
+            Err(RPMError::UnexpectedTagDataType { .. })
+        ));
+        assert!(header
+            .set_string(IndexTag::RPMTAG_HEADERIMMUTABLE, "")
+            .is_err());
+        assert!(header.remove(IndexTag::RPMTAG_NAME));
+        assert!(!header.remove(IndexTag::RPMTAG_NAME));
+
+        assert_eq!("2", header.get_release()?);
+        assert_eq!(1, header.get_epoch()?);
+        assert_eq!(
+            vec![
+                IndexTag::RPMTAG_HEADERIMMUTABLE,
+                IndexTag::RPMTAG_RELEASE,
+                IndexTag::RPMTAG_EPOCH,
+                IndexTag::RPMTAG_SUMMARY,
+                IndexTag::RPMTAG_DISTTAG,
+            ],
+            header
+                .index_entries
+                .iter()
+                .map(|entry| entry.tag)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            IndexData::I18NString(vec!["new summary".to_string()]),
+            header.find_entry_or_err(&IndexTag::RPMTAG_SUMMARY)?.data
+        );
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes)?;
+        let parsed = Header::<IndexTag>::parse(&mut bytes.as_slice())?;
+        assert_eq!(header.index_entries, parsed.index_entries);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_and_write_strings() -> Result<(), Box<dyn std::error::Error>> {
+        let header = Header::<IndexTag>::from_entries(
+            vec![
+                IndexEntry::new(
+                    IndexTag::RPMTAG_SUMMARY,
+                    0,
+                    IndexData::I18NString(vec![
+                        "summary".to_string(),
+                        "Zusammenfassung".to_string(),
+                    ]),
+                ),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_BASENAMES,
+                    0,
+                    IndexData::StringArray(vec!["a".to_string(), "b".to_string()]),
+                ),
+            ],
+            IndexTag::RPMTAG_HEADERIMMUTABLE,
+        );
+        let mut bytes = Vec::new();
+        header.write(&mut bytes)?;
+
+        let mut parsed = Header::<IndexTag>::parse(&mut bytes.as_slice())?;
+        assert_eq!(header.index_entries, parsed.index_entries);
+        assert_eq!(Some(&bytes), parsed.raw.as_ref());
+
+        // the reserved bytes of the index header are ignored, and kept
+        bytes[4..8].copy_from_slice(&[1, 2, 3, 4]);
+        parsed = Header::<IndexTag>::parse(&mut bytes.as_slice())?;
+        let mut written = Vec::new();
+        parsed.write(&mut written)?;
+        assert_eq!(bytes, written);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_unknown_entries() -> Result<(), Box<dyn std::error::Error>> {
+        // a tag of a newer rpm version
+        let unknown_tag = 123_456u32;
+        assert!(<IndexTag as num::FromPrimitive>::from_u32(unknown_tag).is_none());
+
+        let header = Header::<IndexTag>::from_entries(
+            vec![
+                IndexEntry::new(
+                    IndexTag::RPMTAG_NAME,
+                    0,
+                    IndexData::StringTag("name".to_string()),
+                ),
+                IndexEntry::new(
+                    IndexTag::RPMTAG_RELEASE,
+                    0,
+                    IndexData::StringTag("1".to_string()),
+                ),
+            ],
+            IndexTag::RPMTAG_HEADERIMMUTABLE,
+        );
+        let mut bytes = Vec::new();
+        header.write(&mut bytes)?;
+        // the entry of RPMTAG_NAME follows the index header and the region entry
+        bytes[32..36].copy_from_slice(&unknown_tag.to_be_bytes());
+
+        let mut parsed = Header::<IndexTag>::parse(&mut bytes.as_slice())?;
+        assert!(parsed.get(IndexTag::RPMTAG_NAME).is_none());
+        let unknown = parsed.unknown_entries().collect::<Vec<_>>();
+        assert_eq!(1, unknown.len());
+        assert_eq!(unknown_tag, unknown[0].tag());
+        assert_eq!(&IndexData::StringTag("name".to_string()), unknown[0].data());
+        let mut written = Vec::new();
+        parsed.write(&mut written)?;
+        assert_eq!(bytes, written);
+
+        // and survives rebuilding the header
+        parsed.set_string(IndexTag::RPMTAG_RELEASE, "2")?;
+        let mut written = Vec::new();
+        parsed.write(&mut written)?;
+        let reparsed = Header::<IndexTag>::parse(&mut written.as_slice())?;
+        assert_eq!("2", reparsed.get_release()?);
+        assert_eq!(parsed.unknown_entries, reparsed.unknown_entries);
+        assert_eq!(
+            &IndexData::StringTag("name".to_string()),
+            reparsed.unknown_entries[0].data()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_unknown_entries_by_tag() -> Result<(), Box<dyn std::error::Error>> {
+        let unknown_tags = [1003u32, 1006u32];
+        for tag in unknown_tags {
+            assert!(<IndexSignatureTag as num::FromPrimitive>::from_u32(tag).is_none());
+        }
+        let header = Header::<IndexSignatureTag>::from_all_entries(
+            vec![
+                IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_SIZE,
+                    0,
+                    IndexData::Int32(vec![1]),
+                ),
+                IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_PGP,
+                    0,
+                    IndexData::Bin(vec![1, 2, 3]),
+                ),
+                IndexEntry::new(
+                    IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE,
+                    0,
+                    IndexData::Int32(vec![2]),
+                ),
+            ],
+            unknown_tags
+                .iter()
+                .map(|&tag| RawIndexEntry {
+                    tag,
+                    data: IndexData::Int64(vec![tag as i64]),
+                    offset: 0,
+                    num_items: 1,
+                })
+                .collect(),
+            IndexSignatureTag::HEADER_SIGNATURES,
+        );
+        let mut bytes = Vec::new();
+        header.write_signature(&mut bytes)?;
+        assert_eq!(bytes.len(), header.written_len());
+
+        let index = bytes[16..16 + 16 * 6]
+            .chunks_exact(16)
+            .map(|entry| RawIndexEntry::parse::<IndexSignatureTag>(entry).map(|(_, entry)| entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec![62, 1000, 1002, 1003, 1006, 1007],
+            index.iter().map(RawIndexEntry::tag).collect::<Vec<_>>()
+        );
+        // the store is laid out in the same order, excluding the region entry
+        assert!(index[1..]
+            .windows(2)
+            .all(|pair| pair[0].offset < pair[1].offset));
+
+        let parsed = Header::<IndexSignatureTag>::parse(&mut bytes.as_slice())?;
+        assert_eq!(header.unknown_entries, parsed.unknown_entries);
+        assert_eq!(header.index_entries, parsed.index_entries);
+        Ok(())
+    }
 }
 
 /// A header keeping track of all other headerr records.
@@ -800,27 +1446,33 @@ impl IndexHeader {
     }
 }
 
-/// A singel entry within the [`IndexHeader`](self::IndexHeader)
+/// A single entry of a [`Header`], see [`Header::entries`].
 #[derive(Debug, PartialEq)]
-pub(crate) struct IndexEntry<T: num::FromPrimitive> {
+pub struct IndexEntry<T: num::FromPrimitive> {
     pub(crate) tag: T,
     pub(crate) data: IndexData,
     pub(crate) offset: i32,
     pub(crate) num_items: u32,
 }
 
+/// An entry of a [`Header`] with its raw tag, see [`Header::unknown_entries`].
+#[derive(Debug, PartialEq)]
+pub struct RawIndexEntry {
+    pub(crate) tag: u32,
+    pub(crate) data: IndexData,
+    pub(crate) offset: i32,
+    pub(crate) num_items: u32,
+}
+
 use crate::constants::TypeName;
 
-impl<T: num::FromPrimitive + num::ToPrimitive + fmt::Debug + TypeName> IndexEntry<T> {
+impl RawIndexEntry {
     // 16 bytes
-    pub(crate) fn parse(input: &[u8]) -> Result<(&[u8], Self), RPMError> {
+    /// Parse an entry of a header of `T`, with the raw tag since `T` may lack it.
+    pub(crate) fn parse<T: TypeName>(input: &[u8]) -> Result<(&[u8], RawIndexEntry), RPMError> {
         //first 4 bytes are the tag.
-        let (input, raw_tag) = be_u32(input)?;
+        let (input, tag) = be_u32(input)?;
 
-        let tag: T = num::FromPrimitive::from_u32(raw_tag).ok_or_else(|| RPMError::InvalidTag {
-            raw_tag: raw_tag,
-            store_type: T::type_name(),
-        })?;
         //next 4 bytes is the tag type
         let (input, raw_tag_type) = be_u32(input)?;
 
@@ -839,7 +1491,7 @@ impl<T: num::FromPrimitive + num::ToPrimitive + fmt::Debug + TypeName> IndexEntr
 
         Ok((
             rest,
-            IndexEntry {
+            RawIndexEntry {
                 tag,
                 data,
                 offset,
@@ -848,6 +1500,72 @@ impl<T: num::FromPrimitive + num::ToPrimitive + fmt::Debug + TypeName> IndexEntr
         ))
     }
 
+    #[cfg(feature = "async-tokio")]
+    pub(crate) async fn write_index_async<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        out: &mut W,
+    ) -> Result<(), RPMError> {
+        let mut written = out.write(&self.tag.to_be_bytes()).await?;
+        written += out.write(&self.data.to_u32().to_be_bytes()).await?;
+        written += out.write(&self.offset.to_be_bytes()).await?;
+        written += out.write(&self.num_items.to_be_bytes()).await?;
+        assert_eq!(16, written, "there should be 16 bytes written");
+        Ok(())
+    }
+
+    pub(crate) fn write_index<W: std::io::Write>(&self, out: &mut W) -> Result<(), RPMError> {
+        let mut written = out.write(&self.tag.to_be_bytes())?;
+        written += out.write(&self.data.to_u32().to_be_bytes())?;
+        written += out.write(&self.offset.to_be_bytes())?;
+        written += out.write(&self.num_items.to_be_bytes())?;
+        assert_eq!(16, written, "there should be 16 bytes written");
+        Ok(())
+    }
+
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// The data, whose [`to_u32`](IndexData::to_u32) is the type of the entry.
+    pub fn data(&self) -> &IndexData {
+        &self.data
+    }
+
+    /// The count of the entry, as stored in the index.
+    pub fn num_items(&self) -> u32 {
+        self.num_items
+    }
+}
+
+/// Position of an entry in the index, see [`index_order`].
+#[derive(Debug, Clone, Copy)]
+enum IndexSlot {
+    Known(usize),
+    Unknown(usize),
+}
+
+/// The order of the `known` and `unknown` entries in the index.
+///
+/// The `known` ones keep their order, the `unknown` ones are merged in by tag
+/// since rpm expects the index to be sorted.
+fn index_order<T: num::FromPrimitive + num::ToPrimitive>(
+    known: &[IndexEntry<T>],
+    unknown: &[RawIndexEntry],
+) -> Vec<IndexSlot> {
+    let mut order = Vec::with_capacity(known.len() + unknown.len());
+    let mut unknown_slots = (0..unknown.len()).peekable();
+    for (i, entry) in known.iter().enumerate() {
+        let tag = entry.tag.to_u32().unwrap_or(u32::MAX);
+        while let Some(j) = unknown_slots.next_if(|&j| unknown[j].tag < tag) {
+            order.push(IndexSlot::Unknown(j));
+        }
+        order.push(IndexSlot::Known(i));
+    }
+    order.extend(unknown_slots.map(IndexSlot::Unknown));
+    order
+}
+
+impl<T: num::FromPrimitive + num::ToPrimitive> IndexEntry<T> {
     #[cfg(feature = "async-tokio")]
     pub(crate) async fn write_index_async<W: tokio::io::AsyncWrite + Unpin>(
         &self,
@@ -880,9 +1598,25 @@ impl<T: num::FromPrimitive + num::ToPrimitive + fmt::Debug + TypeName> IndexEntr
     }
 }
 
-/// Data as present in a [`IndexEntry`](self::IndexEntry) .
+impl<T: num::FromPrimitive + Copy> IndexEntry<T> {
+    pub fn tag(&self) -> T {
+        self.tag
+    }
+
+    /// The data, whose [`to_u32`](IndexData::to_u32) is the type of the entry.
+    pub fn data(&self) -> &IndexData {
+        &self.data
+    }
+
+    /// The count of the entry, as stored in the index.
+    pub fn num_items(&self) -> u32 {
+        self.num_items
+    }
+}
+
+/// Data as present in a [`IndexEntry`](self::IndexEntry), one variant per rpm data type.
 #[derive(Debug, PartialEq, Eq)]
-pub(crate) enum IndexData {
+pub enum IndexData {
     Null,
     Char(Vec<u8>),
     Int8(Vec<i8>),
@@ -892,12 +1626,20 @@ pub(crate) enum IndexData {
     StringTag(String),
     Bin(Vec<u8>),
     StringArray(Vec<String>),
+    /// A translatable string, the untranslated one first, i.e. `RPMTAG_SUMMARY`.
     I18NString(Vec<String>),
 }
 
 impl fmt::Display for IndexData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let rep = match self {
+        write!(f, "{}", self.type_name())
+    }
+}
+
+impl IndexData {
+    /// The name of the data type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
             IndexData::Null => "Null",
             IndexData::Bin(_) => "Bin",
             IndexData::Char(_) => "Char",
@@ -908,8 +1650,7 @@ impl fmt::Display for IndexData {
             IndexData::Int16(_) => "i16",
             IndexData::Int32(_) => "i32",
             IndexData::Int64(_) => "i64",
-        };
-        write!(f, "{}", rep)
+        }
     }
 }
 
@@ -994,7 +1735,10 @@ impl IndexData {
         }
     }
 
-    pub(crate) fn num_items(&self) -> u32 {
+    /// The number of items, the count of the index entry.
+    ///
+    /// A string is a single item, binary data has one item per byte.
+    pub fn num_items(&self) -> u32 {
         match self {
             IndexData::Null => 0,
             IndexData::Bin(items) => items.len() as u32,
@@ -1023,7 +1767,8 @@ impl IndexData {
             _ => None,
         }
     }
-    pub(crate) fn to_u32(&self) -> u32 {
+    /// The number of the data type, as stored in the index.
+    pub fn to_u32(&self) -> u32 {
         match self {
             IndexData::Null => 0,
             IndexData::Char(_) => 1,
@@ -1039,37 +1784,36 @@ impl IndexData {
         }
     }
 
-    pub(crate) fn as_str(&self) -> Option<&str> {
+    pub fn as_str(&self) -> Option<&str> {
         match self {
             IndexData::StringTag(s) => Some(&s),
             _ => None,
         }
     }
 
-    #[allow(unused)]
-    pub(crate) fn as_char_array(&self) -> Option<Vec<u8>> {
+    pub fn as_char_array(&self) -> Option<Vec<u8>> {
         match self {
             IndexData::Char(s) => Some(s.to_vec()),
             _ => None,
         }
     }
 
-    #[allow(unused)]
-    pub(crate) fn as_i8_array(&self) -> Option<Vec<i8>> {
+    pub fn as_i8_array(&self) -> Option<Vec<i8>> {
         match self {
             IndexData::Int8(s) => Some(s.to_vec()),
             _ => None,
         }
     }
 
-    pub(crate) fn as_i16_array(&self) -> Option<Vec<i16>> {
+    pub fn as_i16_array(&self) -> Option<Vec<i16>> {
         match self {
             IndexData::Int16(s) => Some(s.to_vec()),
             _ => None,
         }
     }
 
-    pub(crate) fn as_i32(&self) -> Option<i32> {
+    /// The first item of 32 bit integers.
+    pub fn as_i32(&self) -> Option<i32> {
         match self {
             IndexData::Int32(s) => {
                 if !s.is_empty() {
@@ -1081,14 +1825,15 @@ impl IndexData {
             _ => None,
         }
     }
-    pub(crate) fn as_i32_array(&self) -> Option<Vec<i32>> {
+    pub fn as_i32_array(&self) -> Option<Vec<i32>> {
         match self {
             IndexData::Int32(s) => Some(s.to_vec()),
             _ => None,
         }
     }
 
-    pub(crate) fn as_i64(&self) -> Option<i64> {
+    /// The first item of 64 bit integers.
+    pub fn as_i64(&self) -> Option<i64> {
         match self {
             IndexData::Int64(s) => {
                 if !s.is_empty() {
@@ -1101,21 +1846,22 @@ impl IndexData {
         }
     }
 
-    pub(crate) fn as_i64_array(&self) -> Option<Vec<i64>> {
+    pub fn as_i64_array(&self) -> Option<Vec<i64>> {
         match self {
             IndexData::Int64(s) => Some(s.to_vec()),
             _ => None,
         }
     }
 
-    pub(crate) fn as_string_array(&self) -> Option<&[String]> {
+    /// The items of a string array or of a translatable string.
+    pub fn as_string_array(&self) -> Option<&[String]> {
         match self {
             IndexData::StringArray(d) | IndexData::I18NString(d) => Some(&d),
             _ => None,
         }
     }
 
-    pub(crate) fn as_binary(&self) -> Option<&[u8]> {
+    pub fn as_binary(&self) -> Option<&[u8]> {
         match self {
             IndexData::Bin(d) => Some(d.as_slice()),
             _ => None,
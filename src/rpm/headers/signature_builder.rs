@@ -33,6 +33,7 @@ where
     T: ConstructionStage,
 {
     entries: Vec<IndexEntry<IndexSignatureTag>>,
+    reserved_space: usize,
     phantom: std::marker::PhantomData<T>,
 }
 
@@ -40,6 +41,7 @@ impl SignatureHeaderBuilder<Empty> {
     pub fn new() -> Self {
         Self {
             entries: Vec::with_capacity(10),
+            reserved_space: RESERVED_SPACE_SIZE,
             phantom: Default::default(),
         }
     }
@@ -50,15 +52,22 @@ where
     T: ConstructionStage,
 {
     /// Construct the complete signature header.
-    pub fn build(mut self, signature_size: i32) -> Header<IndexSignatureTag> {
-        self.entries.insert(
-            0,
-            IndexEntry::new(
-                IndexSignatureTag::RPMSIGTAG_SIZE,
+    ///
+    /// `signature_size` is the combined size of header and payload. Beyond 4 GiB
+    /// it is stored as `RPMSIGTAG_LONGSIGSIZE` instead of `RPMSIGTAG_SIZE`.
+    ///
+    /// Unless disabled with [`with_reserved_space`](Self::with_reserved_space), space
+    /// for signatures added later on is reserved as `RPMSIGTAG_RESERVEDSPACE`.
+    pub fn build(mut self, signature_size: u64) -> Header<IndexSignatureTag> {
+        self.entries.insert(0, size_entry(signature_size));
+        if self.reserved_space > 0 {
+            // last, so resizing it does not affect the alignment of other entries
+            self.entries.push(IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE,
                 0i32, // externally filled
-                IndexData::Int32(vec![signature_size]),
-            ),
-        );
+                IndexData::Bin(vec![0u8; self.reserved_space]),
+            ));
+        }
 
         Header::<IndexSignatureTag>::from_entries(
             self.entries,
@@ -67,6 +76,40 @@ where
     }
 }
 
+impl<T> SignatureHeaderBuilder<T>
+where
+    T: ConstructionStage,
+{
+    /// the number of bytes to reserve for signatures added later on, 0 to reserve none
+    ///
+    /// Defaults to [`RESERVED_SPACE_SIZE`], enough for several 4096 bit RSA signatures.
+    pub fn with_reserved_space(mut self, reserved_space: usize) -> Self {
+        self.reserved_space = reserved_space;
+        self
+    }
+
+    /// add the uncompressed size of the payload, including the cpio headers
+    ///
+    /// Beyond 4 GiB it is stored as `RPMSIGTAG_LONGARCHIVESIZE` instead of `RPMSIGTAG_PAYLOADSIZE`.
+    pub fn add_payload_size(mut self, archive_size: u64) -> Self {
+        let offset = 0i32; // filled externally later on
+        if archive_size > super::large_size_threshold() {
+            self.entries.push(IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE,
+                offset,
+                IndexData::Int64(vec![archive_size as i64]),
+            ));
+        } else {
+            self.entries.push(IndexEntry::new(
+                IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE,
+                offset,
+                IndexData::Int32(vec![archive_size as u32 as i32]),
+            ));
+        }
+        self
+    }
+}
+
 impl SignatureHeaderBuilder<Empty> {
     /// add a digest over the header and a signature accross header and source excluding the static lead
     pub fn add_digest(
@@ -87,34 +130,204 @@ impl SignatureHeaderBuilder<Empty> {
         ));
         SignatureHeaderBuilder::<WithDigest> {
             entries: self.entries,
+            reserved_space: self.reserved_space,
+            phantom: Default::default(),
+        }
+    }
+
+    /// add a sha256 digest over the header only, omitting the legacy md5 and sha1 digests
+    ///
+    /// Those are rejected by rpm running in FIPS mode.
+    pub fn add_sha256_digest(
+        self,
+        digest_header_sha256: &str,
+    ) -> SignatureHeaderBuilder<WithDigest> {
+        SignatureHeaderBuilder::<WithDigest> {
+            entries: self.entries,
+            reserved_space: self.reserved_space,
             phantom: Default::default(),
         }
+        .add_sha256_digest(digest_header_sha256)
     }
 }
 
 impl SignatureHeaderBuilder<WithDigest> {
+    /// add a sha256 digest over the header only
+    pub fn add_sha256_digest(mut self, digest_header_sha256: &str) -> Self {
+        let offset = 0i32; // filled externally later on
+        self.entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_SHA256,
+            offset,
+            IndexData::StringTag(digest_header_sha256.to_string()),
+        ));
+        self
+    }
+
     /// add a signature over the header and a signature accross header and source excluding the static lead
+    ///
+    /// Like rpm, signatures made with RSA keys are stored as `RPMSIGTAG_RSA` and `RPMSIGTAG_PGP`,
+    /// all others (DSA, ECDSA, EdDSA) as `RPMSIGTAG_DSA` and `RPMSIGTAG_GPG`.
     pub fn add_signature(
         mut self,
         rsa_sig_header_only: &[u8],
         rsa_sig_header_and_archive: &[u8],
     ) -> SignatureHeaderBuilder<WithSignature> {
-        let offset = 0i32; // filled externally later on
-        self.entries.push(IndexEntry::new(
-            IndexSignatureTag::RPMSIGTAG_RSA,
-            offset,
-            IndexData::Bin(rsa_sig_header_only.to_vec()),
-        ));
-        self.entries.push(IndexEntry::new(
-            IndexSignatureTag::RPMSIGTAG_PGP,
-            offset,
-            IndexData::Bin(rsa_sig_header_and_archive.to_vec()),
-        ));
+        push_signature(
+            &mut self.entries,
+            rsa_sig_header_only,
+            rsa_sig_header_and_archive,
+        );
         SignatureHeaderBuilder::<WithSignature> {
             entries: self.entries,
+            reserved_space: self.reserved_space,
             phantom: Default::default(),
         }
     }
+
+    /// add a signature over the header only, as rpm 4.16 and later create by default
+    ///
+    /// It is stored in the same tag as the header signature of [`add_signature`](Self::add_signature).
+    pub fn add_header_signature(
+        mut self,
+        sig_header_only: &[u8],
+    ) -> SignatureHeaderBuilder<WithSignature> {
+        push_header_signature(&mut self.entries, sig_header_only);
+        SignatureHeaderBuilder::<WithSignature> {
+            entries: self.entries,
+            reserved_space: self.reserved_space,
+            phantom: Default::default(),
+        }
+    }
+
+    /// add a signature over the header to `RPMSIGTAG_OPENPGP`
+    ///
+    /// Unlike [`add_signature`](Self::add_signature), any number of signatures can be
+    /// added this way, i.e. by both the old and the new key during a key transition.
+    /// Only rpm 6 and later verify them, so older rpm versions require an additional
+    /// signature added with [`add_signature`](Self::add_signature).
+    pub fn add_openpgp_signature(
+        mut self,
+        signature: &[u8],
+    ) -> SignatureHeaderBuilder<WithSignature> {
+        push_openpgp_signature(&mut self.entries, signature);
+        SignatureHeaderBuilder::<WithSignature> {
+            entries: self.entries,
+            reserved_space: self.reserved_space,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl SignatureHeaderBuilder<WithSignature> {
+    /// add a further signature over the header to `RPMSIGTAG_OPENPGP`
+    ///
+    /// See [`SignatureHeaderBuilder::<WithDigest>::add_openpgp_signature`].
+    pub fn add_openpgp_signature(mut self, signature: &[u8]) -> Self {
+        push_openpgp_signature(&mut self.entries, signature);
+        self
+    }
+}
+
+/// All tags holding signatures, as opposed to digests.
+pub(crate) const SIGNATURE_TAGS: [IndexSignatureTag; 5] = [
+    IndexSignatureTag::RPMSIGTAG_RSA,
+    IndexSignatureTag::RPMSIGTAG_PGP,
+    IndexSignatureTag::RPMSIGTAG_DSA,
+    IndexSignatureTag::RPMSIGTAG_GPG,
+    IndexSignatureTag::RPMSIGTAG_OPENPGP,
+];
+
+/// The combined size of header and payload, beyond 4 GiB as `RPMSIGTAG_LONGSIGSIZE`.
+pub(crate) fn size_entry(signature_size: u64) -> IndexEntry<IndexSignatureTag> {
+    if signature_size > super::large_size_threshold() {
+        IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE,
+            0i32, // externally filled
+            IndexData::Int64(vec![signature_size as i64]),
+        )
+    } else {
+        IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_SIZE,
+            0i32, // externally filled
+            IndexData::Int32(vec![signature_size as u32 as i32]),
+        )
+    }
+}
+
+/// Append a header only and a header and archive signature in the tags for their algorithm.
+pub(crate) fn push_signature(
+    entries: &mut Vec<IndexEntry<IndexSignatureTag>>,
+    header_only: &[u8],
+    header_and_archive: &[u8],
+) {
+    let (header_only_tag, header_and_archive_tag) = signature_tags(header_only);
+    let offset = 0i32; // filled externally later on
+    entries.push(IndexEntry::new(
+        header_only_tag,
+        offset,
+        IndexData::Bin(header_only.to_vec()),
+    ));
+    entries.push(IndexEntry::new(
+        header_and_archive_tag,
+        offset,
+        IndexData::Bin(header_and_archive.to_vec()),
+    ));
+}
+
+/// Append a header only signature in the tag for its algorithm.
+pub(crate) fn push_header_signature(
+    entries: &mut Vec<IndexEntry<IndexSignatureTag>>,
+    header_only: &[u8],
+) {
+    let (header_only_tag, _) = signature_tags(header_only);
+    entries.push(IndexEntry::new(
+        header_only_tag,
+        0i32, // filled externally later on
+        IndexData::Bin(header_only.to_vec()),
+    ));
+}
+
+/// Append `signature` to the `RPMSIGTAG_OPENPGP` entry, creating it if necessary.
+pub(crate) fn push_openpgp_signature(
+    entries: &mut Vec<IndexEntry<IndexSignatureTag>>,
+    signature: &[u8],
+) {
+    let encoded = base64::encode(signature);
+    let existing = entries
+        .iter_mut()
+        .find(|entry| entry.tag == IndexSignatureTag::RPMSIGTAG_OPENPGP);
+    match existing {
+        Some(entry) => {
+            if let IndexData::StringArray(signatures) = &mut entry.data {
+                signatures.push(encoded);
+            } else {
+                entry.data = IndexData::StringArray(vec![encoded]);
+            }
+            entry.num_items = entry.data.num_items();
+        }
+        None => entries.push(IndexEntry::new(
+            IndexSignatureTag::RPMSIGTAG_OPENPGP,
+            0i32, // filled externally later on
+            IndexData::StringArray(vec![encoded]),
+        )),
+    }
+}
+
+/// The tags to store a header only and a header and archive signature in.
+pub(crate) fn signature_tags(signature: &[u8]) -> (IndexSignatureTag, IndexSignatureTag) {
+    use crate::signature::packet;
+
+    match packet::public_key_algorithm(signature) {
+        // unparsable signatures are kept where they always were
+        None | Some(packet::PUBKEY_ALGO_RSA) | Some(packet::PUBKEY_ALGO_RSA_SIGN_ONLY) => (
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP,
+        ),
+        Some(_) => (
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+        ),
+    }
 }
 
 #[cfg(test)]
@@ -132,7 +345,7 @@ mod test {
         let header = builder
             .add_digest("", &digest_header_and_archive[..])
             .add_signature(&rsa_sig_header_only[..], &rsa_sig_header_and_archive[..])
-            .build(32i32);
+            .build(32u64);
 
         assert!(header
             .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RSA)
@@ -147,4 +360,116 @@ mod test {
             .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SHA1)
             .is_ok());
     }
+
+    #[test]
+    fn signature_builder_openpgp_signatures() {
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .add_sha256_digest("")
+            .add_signature(&[1u8; 32][..], &[2u8; 32][..])
+            .add_openpgp_signature(&[3u8; 32][..])
+            .add_openpgp_signature(&[4u8; 32][..])
+            .build(32u64);
+        assert_eq!(
+            vec![vec![3u8; 32], vec![4u8; 32]],
+            header.get_openpgp_signatures().unwrap()
+        );
+
+        let mut header = SignatureHeaderBuilder::<Empty>::new()
+            .add_sha256_digest("")
+            .add_openpgp_signature(&[3u8; 32][..])
+            .build(32u64);
+        header.add_openpgp_signature(&[4u8; 32][..]);
+        assert_eq!(
+            vec![vec![3u8; 32], vec![4u8; 32]],
+            header.get_openpgp_signatures().unwrap()
+        );
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SHA256)
+            .is_ok());
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RSA)
+            .is_err());
+    }
+
+    #[test]
+    fn signature_builder_without_legacy_digests() {
+        let digest_header_sha256 = "ab".repeat(32);
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .add_sha256_digest(digest_header_sha256.as_str())
+            .add_signature(&[0u8; 32][..], &[0u8; 32][..])
+            .build(32u64);
+
+        assert_eq!(
+            header
+                .get_entry_string_data(IndexSignatureTag::RPMSIGTAG_SHA256)
+                .unwrap(),
+            digest_header_sha256
+        );
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_MD5)
+            .is_err());
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SHA1)
+            .is_err());
+    }
+
+    #[test]
+    fn signature_builder_long_sizes() {
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .add_digest("", &[0u8; 16][..])
+            .add_payload_size(5 * 1024 * 1024 * 1024)
+            .build(6 * 1024 * 1024 * 1024);
+
+        assert_eq!(
+            header
+                .get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE)
+                .unwrap(),
+            6 * 1024 * 1024 * 1024
+        );
+        assert_eq!(
+            header
+                .get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE)
+                .unwrap(),
+            5 * 1024 * 1024 * 1024
+        );
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SIZE)
+            .is_err());
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)
+            .is_err());
+    }
+
+    #[test]
+    fn signature_builder_reserved_space() {
+        let mut header = SignatureHeaderBuilder::<Empty>::new()
+            .add_sha256_digest("")
+            .add_signature(&[1u8; 32][..], &[2u8; 32][..])
+            .build(32u64);
+        assert_eq!(
+            header
+                .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE)
+                .unwrap()
+                .data,
+            IndexData::Bin(vec![0u8; RESERVED_SPACE_SIZE])
+        );
+
+        // a larger signature is taken from the reserved space
+        let len = header.written_len();
+        header.remove_signatures();
+        header.add_signature(&[1u8; 512][..], &[2u8; 512][..]);
+        header.fit_reserved_space(len).unwrap();
+        assert_eq!(header.written_len(), len);
+        assert!(header
+            .fit_reserved_space(len - RESERVED_SPACE_SIZE)
+            .is_err());
+
+        let header = SignatureHeaderBuilder::<Empty>::new()
+            .with_reserved_space(0)
+            .add_sha256_digest("")
+            .build(32u64);
+        assert!(header
+            .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_RESERVEDSPACE)
+            .is_err());
+    }
 }
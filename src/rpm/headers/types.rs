@@ -3,7 +3,7 @@ use crate::{constants::*, errors};
 
 /// Describes a file present in the rpm file.
 pub struct RPMFileEntry {
-    pub(crate) size: i32,
+    pub(crate) size: u64,
     pub(crate) mode: FileMode,
     pub(crate) modified_at: i32,
     pub(crate) sha_checksum: String,
@@ -308,6 +308,19 @@ impl Dependency {
         Self::new(dep_name.into(), RPMSENSE_ANY, "".to_string())
     }
 
+    /// Internal requirement on a capability of rpm itself, i.e. `rpmlib(LargeFiles)`.
+    pub(crate) fn rpm_lib<E, T>(dep_name: T, version: E) -> Self
+    where
+        T: Into<String>,
+        E: Into<String>,
+    {
+        Self::new(
+            dep_name.into(),
+            RPMSENSE_RPMLIB | RPMSENSE_LESS | RPMSENSE_EQUAL,
+            version.into(),
+        )
+    }
+
     fn new(dep_name: String, sense: u32, version: String) -> Self {
         Dependency {
             dep_name,
@@ -317,6 +330,24 @@ impl Dependency {
     }
 }
 
+/// Sizes beyond this need the 64 bit tags, i.e. `RPMTAG_LONGSIZE` or `RPMSIGTAG_LONGSIGSIZE`.
+#[cfg(not(test))]
+pub(crate) fn large_size_threshold() -> u64 {
+    u32::MAX as u64
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Lowered by tests, packages beyond 4 GiB would not fit into memory.
+    pub(crate) static LARGE_SIZE_THRESHOLD: std::cell::Cell<u64> =
+        const { std::cell::Cell::new(u32::MAX as u64) };
+}
+
+#[cfg(test)]
+pub(crate) fn large_size_threshold() -> u64 {
+    LARGE_SIZE_THRESHOLD.with(|threshold| threshold.get())
+}
+
 mod test {
 
     #[test]
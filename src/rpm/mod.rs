@@ -2,6 +2,8 @@ mod builder;
 mod compressor;
 mod headers;
 mod package;
+mod payload;
+mod verification;
 
 #[cfg(feature = "signature-meta")]
 pub mod signature;
@@ -12,4 +14,6 @@ pub use compressor::*;
 
 pub use package::*;
 
+pub use verification::*;
+
 pub use builder::*;
@@ -8,10 +8,12 @@ use crate::sequential_cursor::SeqCursor;
 
 use crate::errors::*;
 
+use super::verification::{self, DigestReport, DigestStatus, FileDigestReport};
+#[cfg(feature = "signature-meta")]
+use super::verification::{SignatureCheck, SignatureInfo, SignatureStatus, VerificationReport};
 use super::Lead;
 use crate::signature;
 
-use std::io::{Read, Seek, SeekFrom};
 /// A complete rpm file.
 ///
 /// Can either be created using the [`RPMPackageBuilder`](super::builder::RPMPackageBuilder)
@@ -42,12 +44,14 @@ impl RPMPackage {
         Ok(RPMPackage { metadata, content })
     }
 
+    /// Write the package, a parsed one byte for byte as it was read unless modified.
     pub fn write<W: std::io::Write>(&self, out: &mut W) -> Result<(), RPMError> {
         self.metadata.write(out)?;
         out.write_all(&self.content)?;
         Ok(())
     }
 
+    /// Asynchronous variant of [`write`](Self::write).
     pub async fn write_async<W: tokio::io::AsyncWrite + Unpin>(
         &self,
         out: &mut W,
@@ -57,99 +61,680 @@ impl RPMPackage {
         Ok(())
     }
 
+    /// Recompute all digests present in the signature header as well as the payload digests.
+    ///
+    /// Does not require any key, equivalent to `rpm -K --nosignature`.
+    /// Fails for an unknown payload digest algorithm or a payload which can not be decompressed.
+    /// Use [`DigestReport::is_ok`] to check if the package is intact.
+    pub fn verify_digests(&self) -> Result<DigestReport, RPMError> {
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
+
+        let signature = &self.metadata.signature;
+
+        let header_sha1 = match signature.get_entry_string_data(IndexSignatureTag::RPMSIGTAG_SHA1) {
+            Ok(expected) => DigestStatus::compare(
+                expected,
+                verification::digest_hex(FileDigestAlgorithm::Sha1, header_bytes.as_slice())?,
+            ),
+            Err(_) => DigestStatus::NotPresent,
+        };
+
+        let header_sha256 = match signature
+            .get_entry_string_data(IndexSignatureTag::RPMSIGTAG_SHA256)
+        {
+            Ok(expected) => DigestStatus::compare(
+                expected,
+                verification::digest_hex(FileDigestAlgorithm::Sha2_256, header_bytes.as_slice())?,
+            ),
+            Err(_) => DigestStatus::NotPresent,
+        };
+
+        let header_and_payload_md5 =
+            match signature.get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_MD5) {
+                Ok(expected) => DigestStatus::compare(
+                    &hex::encode(expected),
+                    verification::digest_hex(
+                        FileDigestAlgorithm::Md5,
+                        SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]),
+                    )?,
+                ),
+                Err(_) => DigestStatus::NotPresent,
+            };
+
+        let header = &self.metadata.header;
+        let payload_digest = header.get_payload_digest();
+        let payload_digest_alt = header.get_payload_digest_alt();
+        let algorithm = if payload_digest.is_ok() || payload_digest_alt.is_ok() {
+            match header.get_payload_digest_algorithm() {
+                Ok(algorithm) => algorithm,
+                // rpm does not encode it, if it is the default sha256
+                Err(RPMError::TagNotFound(_)) => FileDigestAlgorithm::Sha2_256,
+                Err(e) => return Err(e),
+            }
+        } else {
+            FileDigestAlgorithm::Sha2_256
+        };
+
+        let payload = match payload_digest {
+            Ok(expected) => DigestStatus::compare(
+                expected,
+                verification::digest_hex(algorithm, self.content.as_slice())?,
+            ),
+            Err(_) => DigestStatus::NotPresent,
+        };
+
+        let payload_uncompressed = match payload_digest_alt {
+            Ok(expected) => DigestStatus::compare(
+                expected,
+                verification::digest_hex(
+                    algorithm,
+                    verification::decompress_payload(header, self.content.as_slice())?,
+                )?,
+            ),
+            Err(_) => DigestStatus::NotPresent,
+        };
+
+        Ok(DigestReport {
+            header_sha1,
+            header_sha256,
+            header_and_payload_md5,
+            payload,
+            payload_uncompressed,
+        })
+    }
+
+    /// Verify the content of every file in the payload against the digests
+    /// stored in the header.
+    ///
+    /// The payload is decompressed and streamed, so this is comparatively
+    /// expensive. Files missing from the payload or unknown to the header are
+    /// reported as well.
+    pub fn verify_file_digests(&self) -> Result<FileDigestReport, RPMError> {
+        verification::verify_file_digests(&self.metadata.header, &self.content)
+    }
+
     // TODO allow passing an external signer/verifier
 
     /// sign all headers (except for the lead) using an external key and store it as the initial header
+    ///
+    /// Replaces all present signatures, like `rpmsign --resign`. The digests
+    /// are recomputed, all other entries of the signature header are kept.
     #[cfg(feature = "signature-meta")]
-    pub fn sign<S>(&mut self, signer: S) -> Result<(), RPMError>
+    pub fn sign<S, A>(&mut self, signer: S) -> Result<(), RPMError>
     where
-        S: signature::Signing<signature::algorithm::RSA, Signature = Vec<u8>>,
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A, Signature = Vec<u8>>,
     {
-        // create a temporary byte repr of the header
-        // and re-create all hashes
-        let mut header_bytes = Vec::<u8>::with_capacity(1024);
-        self.metadata.header.write(&mut header_bytes)?;
+        let header_bytes = self.header_bytes()?;
+        let (signature_header_only, signature_header_and_archive) =
+            self.create_signatures(&header_bytes, signer)?;
+        let len = self.metadata.signature.written_len();
+        self.metadata.signature.remove_signatures();
+        self.store_signatures(
+            &header_bytes,
+            &signature_header_only,
+            &signature_header_and_archive,
+        );
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
+
+    /// Asynchronous variant of [`sign`](Self::sign).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn sign_async<S, A>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::AsyncSigning<A, Signature = Vec<u8>>,
+    {
+        let header_bytes = self.header_bytes()?;
+        let (signature_header_only, signature_header_and_archive) =
+            self.create_signatures_async(&header_bytes, signer).await?;
+        let len = self.metadata.signature.written_len();
+        self.metadata.signature.remove_signatures();
+        self.store_signatures(
+            &header_bytes,
+            &signature_header_only,
+            &signature_header_and_archive,
+        );
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
 
-        let mut header_and_content_cursor =
-            SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]);
+    /// Add a signature, keeping all present signatures and other entries of the signature header.
+    ///
+    /// The signatures are stored as `RPMSIGTAG_RSA` and `RPMSIGTAG_PGP` for RSA keys, as
+    /// `RPMSIGTAG_DSA` and `RPMSIGTAG_GPG` for all others. If the package is already signed
+    /// by a key of the same kind, only the header signature is added to `RPMSIGTAG_OPENPGP`
+    /// instead, see [`add_openpgp_signature`](Self::add_openpgp_signature), and the payload
+    /// is not signed at all.
+    #[cfg(feature = "signature-meta")]
+    pub fn add_signature<S, A>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A, Signature = Vec<u8>>,
+    {
+        let header_bytes = self.header_bytes()?;
+        let signature_header_only = signer.sign(header_bytes.as_slice())?;
+        let len = self.metadata.signature.written_len();
+        // a header and payload signature would have nowhere to go, so spare the signer that pass
+        if self
+            .metadata
+            .signature
+            .signature_tags_taken(&signature_header_only)
+        {
+            self.metadata
+                .signature
+                .add_openpgp_signature(&signature_header_only);
+        } else {
+            let header_and_content_cursor =
+                SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]);
+            let signature_header_and_archive = signer.sign(header_and_content_cursor)?;
+            self.store_signatures(
+                &header_bytes,
+                &signature_header_only,
+                &signature_header_and_archive,
+            );
+        }
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
 
-        let mut hasher = md5::Md5::default();
+    /// Asynchronous variant of [`add_signature`](Self::add_signature).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn add_signature_async<S, A>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::AsyncSigning<A, Signature = Vec<u8>>,
+    {
+        let header_bytes = self.header_bytes()?;
+        let signature_header_only = signer.sign(header_bytes.as_slice()).await?;
+        let len = self.metadata.signature.written_len();
+        if self
+            .metadata
+            .signature
+            .signature_tags_taken(&signature_header_only)
         {
-            // avoid loading it into memory all at once
-            // since the content could be multiple 100s of MBs
-            let mut buf = [0u8; 256];
-            while let Ok(n) = header_and_content_cursor.read(&mut buf[..]) {
-                hasher.update(&buf[0..n]);
+            self.metadata
+                .signature
+                .add_openpgp_signature(&signature_header_only);
+        } else {
+            let header_and_content =
+                AsyncReadExt::chain(header_bytes.as_slice(), self.content.as_slice());
+            let signature_header_and_archive = signer.sign(header_and_content).await?;
+            self.store_signatures(
+                &header_bytes,
+                &signature_header_only,
+                &signature_header_and_archive,
+            );
+        }
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
+
+    /// Remove all signatures, like `rpmsign --delsign`.
+    ///
+    /// The digests and all other entries of the signature header are kept.
+    #[cfg(feature = "signature-meta")]
+    pub fn remove_signatures(&mut self) {
+        let len = self.metadata.signature.written_len();
+        self.metadata.signature.remove_signatures();
+        self.restore_signature_header_len(len);
+    }
+
+    /// Replace all signatures of the package `file` like [`sign`](Self::sign)
+    /// does, without loading the payload into memory.
+    ///
+    /// Only the signature header is rewritten, which must keep its size. This works
+    /// for packages with enough `RPMSIGTAG_RESERVEDSPACE`, as built by this crate or
+    /// rpm. Otherwise [`RPMError::InsufficientReservedSpace`] is returned and the
+    /// file is left untouched. The digests are kept, since header and payload are.
+    #[cfg(feature = "signature-meta")]
+    pub fn resign_in_place<F, S, A>(file: &mut F, signer: S) -> Result<(), RPMError>
+    where
+        F: std::io::Read + std::io::Write + std::io::Seek,
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A, Signature = Vec<u8>>,
+    {
+        file.seek(std::io::SeekFrom::Start(0))?;
+        let metadata = RPMPackageMetadata::parse(&mut std::io::BufReader::new(&mut *file))?;
+        let mut signature = metadata.signature;
+        let len = signature.written_len();
+
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        metadata.header.write(&mut header_bytes)?;
+        let signature_header_only = signer.sign(header_bytes.as_slice())?;
+        // the header is followed by the payload up to the end of the file
+        file.seek(std::io::SeekFrom::Start((LEAD_SIZE + len) as u64))?;
+        let signature_header_and_archive = signer.sign(std::io::BufReader::new(&mut *file))?;
+
+        signature.remove_signatures();
+        signature.add_signature(&signature_header_only, &signature_header_and_archive);
+        signature.fit_reserved_space(len)?;
+
+        file.seek(std::io::SeekFrom::Start(LEAD_SIZE as u64))?;
+        signature.write_signature(file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Export the signatures as detached, ASCII armored OpenPGP signatures.
+    ///
+    /// All header signatures, including the ones of `RPMSIGTAG_OPENPGP`, are armored
+    /// together, as are all header and payload signatures. They can be checked with
+    /// `gpg --verify` against the header, respectively the package without its lead
+    /// and signature header.
+    #[cfg(feature = "signature-meta")]
+    pub fn export_detached_signatures(&self) -> Result<DetachedSignatures, RPMError> {
+        let (header, header_and_payload): (Vec<_>, Vec<_>) = self
+            .present_signatures()?
+            .into_iter()
+            .partition(|(tag, _)| Self::spans_header_only(*tag));
+        let armor = |signatures: Vec<(IndexSignatureTag, Vec<u8>)>| {
+            if signatures.is_empty() {
+                return None;
             }
+            let packets = signatures
+                .into_iter()
+                .flat_map(|(_, signature)| signature)
+                .collect::<Vec<u8>>();
+            Some(signature::packet::armor(&packets))
+        };
+        Ok(DetachedSignatures {
+            header: armor(header),
+            header_and_payload: armor(header_and_payload),
+        })
+    }
+
+    /// Attach detached, ASCII armored OpenPGP signatures made elsewhere, i.e. by
+    /// `gpg --armor --detach-sign` on an offline signing host, keeping all present
+    /// signatures and other entries of the signature header.
+    ///
+    /// Each header and payload signature is stored along with the header signature by the
+    /// same key, like [`add_signature`](Self::add_signature) does. The header signatures
+    /// alone are stored like that as well, as rpm 4.16 and later do.
+    ///
+    /// Fails with [`RPMError::NoSignatureFound`] if a signature can not be decoded or a
+    /// header and payload signature lacks the header signature by its key.
+    #[cfg(feature = "signature-meta")]
+    pub fn attach_detached_signatures(
+        &mut self,
+        signatures: &DetachedSignatures,
+    ) -> Result<(), RPMError> {
+        let dearmor = |armored: &Option<String>| -> Result<Vec<u8>, RPMError> {
+            match armored {
+                Some(armored) => {
+                    signature::packet::dearmor(armored).ok_or(RPMError::NoSignatureFound)
+                }
+                None => Ok(Vec::new()),
+            }
+        };
+        let header = dearmor(&signatures.header)?;
+        let header_and_payload = dearmor(&signatures.header_and_payload)?;
+        let split = |packets| {
+            let signatures =
+                signature::packet::split_signatures(packets).ok_or(RPMError::NoSignatureFound)?;
+            signatures
+                .into_iter()
+                .map(|signature| {
+                    let key_id = signature::packet::issuer_key_id(signature)
+                        .ok_or(RPMError::NoSignatureFound)?;
+                    Ok((key_id, signature))
+                })
+                .collect::<Result<Vec<_>, RPMError>>()
+        };
+        let header = split(&header)?;
+        let mut header_and_payload = split(&header_and_payload)?;
+        if header.is_empty() {
+            return Err(RPMError::NoSignatureFound);
         }
-        let hash_result = hasher.finalize();
 
-        header_and_content_cursor.seek(SeekFrom::Start(0))?;
+        // pair them up front, to fail without modifying the signature header
+        let mut pairs = Vec::with_capacity(header.len());
+        for (key_id, header_only) in header {
+            let header_and_archive = header_and_payload
+                .iter()
+                .position(|(other, _)| *other == key_id)
+                .map(|index| header_and_payload.remove(index).1);
+            pairs.push((header_only, header_and_archive));
+        }
+        if !header_and_payload.is_empty() {
+            return Err(RPMError::NoSignatureFound);
+        }
+
+        let len = self.metadata.signature.written_len();
+        for (header_only, header_and_archive) in pairs {
+            match header_and_archive {
+                Some(header_and_archive) => self
+                    .metadata
+                    .signature
+                    .add_signature(header_only, header_and_archive),
+                None => self.metadata.signature.add_header_signature(header_only),
+            }
+        }
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
+
+    /// shrink or grow the reserved space to restore the signature header to `len` bytes
+    #[cfg(feature = "signature-meta")]
+    fn restore_signature_header_len(&mut self, len: usize) {
+        // without enough reserved space, the package can not be re-signed in place
+        // later on, which does not matter for signing it in memory
+        let _ = self.metadata.signature.fit_reserved_space(len);
+    }
+
+    /// a temporary byte repr of the header
+    #[cfg(feature = "signature-meta")]
+    fn header_bytes(&self) -> Result<Vec<u8>, RPMError> {
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
+        Ok(header_bytes)
+    }
+
+    /// a signature spanning the header only and one spanning header and payload
+    #[cfg(feature = "signature-meta")]
+    fn create_signatures<S, A>(
+        &self,
+        header_bytes: &[u8],
+        signer: S,
+    ) -> Result<(Vec<u8>, Vec<u8>), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A, Signature = Vec<u8>>,
+    {
+        let signature_header_only = signer.sign(header_bytes)?;
+
+        let header_and_content_cursor = SeqCursor::new(&[header_bytes, self.content.as_slice()]);
+        let signature_header_and_archive = signer.sign(header_and_content_cursor)?;
+        Ok((signature_header_only, signature_header_and_archive))
+    }
+
+    /// Asynchronous variant of [`create_signatures`](Self::create_signatures).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    async fn create_signatures_async<S, A>(
+        &self,
+        header_bytes: &[u8],
+        signer: S,
+    ) -> Result<(Vec<u8>, Vec<u8>), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::AsyncSigning<A, Signature = Vec<u8>>,
+    {
+        let signature_header_only = signer.sign(header_bytes).await?;
+
+        let header_and_content = AsyncReadExt::chain(header_bytes, self.content.as_slice());
+        let signature_header_and_archive = signer.sign(header_and_content).await?;
+        Ok((signature_header_only, signature_header_and_archive))
+    }
 
+    /// re-create all digests and add the given signatures
+    #[cfg(feature = "signature-meta")]
+    fn store_signatures(
+        &mut self,
+        header_bytes: &[u8],
+        signature_header_only: &[u8],
+        signature_header_and_archive: &[u8],
+    ) {
+        let mut hasher = md5::Md5::default();
+        hasher.update(header_bytes);
+        hasher.update(&self.content);
+        let hash_result = hasher.finalize();
         let digest_md5 = hash_result.as_slice();
 
-        let digest_sha1 = sha1::Sha1::from(&header_bytes);
+        let digest_sha1 = sha1::Sha1::from(header_bytes);
         let digest_sha1 = digest_sha1.digest();
 
-        let rsa_signature_spanning_header_only = signer.sign(header_bytes.as_slice())?;
+        let mut hasher = sha2::Sha256::default();
+        hasher.update(header_bytes);
+        let digest_sha256 = hex::encode(hasher.finalize());
 
-        let rsa_signature_spanning_header_and_archive =
-            signer.sign(&mut header_and_content_cursor)?;
-
-        // TODO FIXME verify this is the size we want, I don't think it is
-        // TODO maybe use signature_size instead of size
-        self.metadata.signature = Header::<IndexSignatureTag>::new_signature_header(
-            header_and_content_cursor.len() as i32,
+        let signature = &mut self.metadata.signature;
+        signature.set_digests(
+            (header_bytes.len() + self.content.len()) as u64,
             digest_md5,
-            digest_sha1.to_string(),
-            rsa_signature_spanning_header_only.as_slice(),
-            rsa_signature_spanning_header_and_archive.as_slice(),
+            &digest_sha1.to_string(),
+            &digest_sha256,
         );
+        signature.add_signature(signature_header_only, signature_header_and_archive);
+    }
+
+    /// Add a signature of the header to `RPMSIGTAG_OPENPGP`, keeping all other signatures.
+    ///
+    /// Any number of signatures can be added this way, i.e. by both the old and the
+    /// new key during a key transition. Only rpm 6 and later verify them, so packages
+    /// meant for older versions need to be [`sign`](Self::sign)ed as well.
+    #[cfg(feature = "signature-meta")]
+    pub fn add_openpgp_signature<S, A>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::Signing<A, Signature = Vec<u8>>,
+    {
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
+
+        let signature = signer.sign(header_bytes.as_slice())?;
+        let len = self.metadata.signature.written_len();
+        self.metadata.signature.add_openpgp_signature(&signature);
+        self.restore_signature_header_len(len);
+        Ok(())
+    }
+
+    /// Asynchronous variant of [`add_openpgp_signature`](Self::add_openpgp_signature).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn add_openpgp_signature_async<S, A>(&mut self, signer: S) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        S: signature::AsyncSigning<A, Signature = Vec<u8>>,
+    {
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
 
+        let signature = signer.sign(header_bytes.as_slice()).await?;
+        let len = self.metadata.signature.written_len();
+        self.metadata.signature.add_openpgp_signature(&signature);
+        self.restore_signature_header_len(len);
         Ok(())
     }
 
     /// Verify the signature as present within the RPM package.
     ///
+    /// Strict variant of [`verify`](Self::verify), which requires both a
+    /// header only and a header and payload signature to be present and
+    /// returns the first failure as error.
     ///
+    /// Signatures by RSA keys are stored as `RPMSIGTAG_RSA` and `RPMSIGTAG_PGP`,
+    /// the ones by other keys, like the DSA keys of older distributions, as
+    /// `RPMSIGTAG_DSA` and `RPMSIGTAG_GPG`. Any number of further header signatures
+    /// may be stored as `RPMSIGTAG_OPENPGP`. Whichever of them are present are
+    /// checked, signatures by keys unknown to the `verifier` are skipped as long
+    /// as another one of the same kind is valid.
+    ///
+    /// Without a valid header and payload signature, as with packages signed only
+    /// by rpm 4.16 and later, the payload digest within the signed header has to be valid.
     #[cfg(feature = "signature-meta")]
-    pub fn verify_signature<V>(&self, verifier: V) -> Result<(), RPMError>
+    pub fn verify_signature<V, A>(&self, verifier: V) -> Result<(), RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        V: signature::Verifying<A, Signature = Vec<u8>>,
+    {
+        Self::require_signatures(self.verify(verifier)?)
+    }
+
+    /// Asynchronous variant of [`verify_signature`](Self::verify_signature).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn verify_signature_async<V, A>(&self, verifier: V) -> Result<(), RPMError>
     where
-        V: signature::Verifying<signature::algorithm::RSA, Signature = Vec<u8>>,
+        A: signature::algorithm::Algorithm,
+        V: signature::AsyncVerifying<A, Signature = Vec<u8>>,
     {
-        // TODO retval should be SIGNATURE_VERIFIED or MISMATCH, not just an error
+        Self::require_signatures(self.verify_async(verifier).await?)
+    }
 
+    /// both a header only and a header and payload signature must be present and valid,
+    /// the latter may be replaced by the payload digest which is part of the header
+    ///
+    /// Of each kind, the first valid one is taken, regardless of its tag. Signatures
+    /// by unknown keys are skipped, all others have to be valid as well.
+    #[cfg(feature = "signature-meta")]
+    fn require_signatures(report: VerificationReport) -> Result<(), RPMError> {
+        let (header_only, header_and_payload): (Vec<_>, Vec<_>) = report
+            .signatures
+            .into_iter()
+            .partition(|check| Self::spans_header_only(check.tag));
+
+        let header_verified = Self::require_any(IndexSignatureTag::RPMSIGTAG_RSA, header_only)?;
+        let header_and_payload_verified =
+            Self::require_any(IndexSignatureTag::RPMSIGTAG_PGP, header_and_payload)?;
+        header_verified?;
+        match header_and_payload_verified {
+            Err(_) if report.digests.payload.is_verified() => Ok(()),
+            res => res,
+        }
+    }
+
+    /// the inner result tells whether one of the `checks` is valid, reporting
+    /// a missing `tag` if there are none, the outer one fails on the first invalid one
+    #[cfg(feature = "signature-meta")]
+    fn require_any(
+        tag: IndexSignatureTag,
+        checks: Vec<SignatureCheck>,
+    ) -> Result<Result<(), RPMError>, RPMError> {
+        let mut verified = None;
+        for check in checks {
+            match check.status {
+                SignatureStatus::Verified => verified = Some(Ok(())),
+                SignatureStatus::Failed(e @ RPMError::KeyNotFoundError { .. }) => {
+                    verified.get_or_insert(Err(e));
+                }
+                SignatureStatus::Failed(e) => return Err(e),
+            }
+        }
+        Ok(verified.unwrap_or_else(|| Err(RPMError::TagNotFound(tag.to_string()))))
+    }
+
+    /// Check all signatures and digests of the signature header.
+    ///
+    /// Failing checks are recorded in the returned report, an error is only
+    /// returned if the package can not be processed at all.
+    #[cfg(feature = "signature-meta")]
+    pub fn verify<V, A>(&self, verifier: V) -> Result<VerificationReport, RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        V: signature::Verifying<A, Signature = Vec<u8>>,
+    {
         let mut header_bytes = Vec::<u8>::with_capacity(1024);
         self.metadata.header.write(&mut header_bytes)?;
 
-        let signature_header_only = self
-            .metadata
-            .signature
-            .get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_RSA)?;
+        let mut signatures = Vec::new();
+        for (tag, signature) in self.present_signatures()? {
+            let res = if Self::spans_header_only(tag) {
+                verifier.verify(header_bytes.as_slice(), &signature)
+            } else {
+                let header_and_content_cursor =
+                    SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]);
+                verifier.verify(header_and_content_cursor, &signature)
+            };
+            signatures.push(SignatureCheck::new(tag, &signature, res));
+        }
 
-        crate::signature::echo_signature("signature_header(header only)", signature_header_only);
+        Ok(VerificationReport {
+            signatures,
+            digests: self.verify_digests()?,
+        })
+    }
 
-        let signature_header_and_content = self
-            .metadata
-            .signature
-            .get_entry_binary_data(IndexSignatureTag::RPMSIGTAG_PGP)?;
+    /// Asynchronous variant of [`verify`](Self::verify).
+    #[cfg(all(feature = "signature-meta", feature = "async-tokio"))]
+    pub async fn verify_async<V, A>(&self, verifier: V) -> Result<VerificationReport, RPMError>
+    where
+        A: signature::algorithm::Algorithm,
+        V: signature::AsyncVerifying<A, Signature = Vec<u8>>,
+    {
+        let mut header_bytes = Vec::<u8>::with_capacity(1024);
+        self.metadata.header.write(&mut header_bytes)?;
 
-        crate::signature::echo_signature(
-            "signature_header(header and content)",
-            signature_header_and_content,
-        );
+        let mut signatures = Vec::new();
+        for (tag, signature) in self.present_signatures()? {
+            let res = if Self::spans_header_only(tag) {
+                verifier.verify(header_bytes.as_slice(), &signature).await
+            } else {
+                let header_and_content =
+                    AsyncReadExt::chain(header_bytes.as_slice(), self.content.as_slice());
+                verifier.verify(header_and_content, &signature).await
+            };
+            signatures.push(SignatureCheck::new(tag, &signature, res));
+        }
 
-        verifier.verify(header_bytes.as_slice(), signature_header_only)?;
+        Ok(VerificationReport {
+            signatures,
+            digests: self.verify_digests()?,
+        })
+    }
 
-        let header_and_content_cursor =
-            SeqCursor::new(&[header_bytes.as_slice(), self.content.as_slice()]);
+    /// Inspect all signatures of the signature header without verifying them.
+    ///
+    /// Reports which key issued each signature, even if its public key is
+    /// not at hand. The signatures of `RPMSIGTAG_OPENPGP` are listed last.
+    /// Fails with [`RPMError::NoSignatureFound`] if a signature can not be parsed.
+    #[cfg(feature = "signature-meta")]
+    pub fn signatures(&self) -> Result<Vec<SignatureInfo>, RPMError> {
+        self.present_signatures()?
+            .into_iter()
+            .map(|(tag, signature)| SignatureInfo::parse(tag, &signature))
+            .collect()
+    }
 
-        verifier.verify(header_and_content_cursor, signature_header_and_content)?;
+    /// all signatures of the signature header, in the order they are verified
+    ///
+    /// The ones of `RPMSIGTAG_OPENPGP` are decoded and come last.
+    #[cfg(feature = "signature-meta")]
+    fn present_signatures(&self) -> Result<Vec<(IndexSignatureTag, Vec<u8>)>, RPMError> {
+        let mut signatures = [
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP,
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+        ]
+        .iter()
+        .filter_map(|&tag| {
+            let signature = self.metadata.signature.get_entry_binary_data(tag).ok()?;
+            crate::signature::echo_signature(&tag.to_string(), signature);
+            Some((tag, signature.to_vec()))
+        })
+        .collect::<Vec<_>>();
 
-        Ok(())
+        let tag = IndexSignatureTag::RPMSIGTAG_OPENPGP;
+        if self.metadata.signature.find_entry_or_err(&tag).is_ok() {
+            for signature in self.metadata.signature.get_openpgp_signatures()? {
+                signatures.push((tag, signature));
+            }
+        }
+        Ok(signatures)
+    }
+
+    #[cfg(feature = "signature-meta")]
+    fn spans_header_only(tag: IndexSignatureTag) -> bool {
+        matches!(
+            tag,
+            IndexSignatureTag::RPMSIGTAG_RSA
+                | IndexSignatureTag::RPMSIGTAG_DSA
+                | IndexSignatureTag::RPMSIGTAG_OPENPGP
+        )
     }
 }
 
+/// Detached signatures of a package, ASCII armored like the `.asc` files of `gpg --armor`.
+///
+/// Each may hold several signatures, i.e. by different keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DetachedSignatures {
+    /// signatures spanning the header only
+    pub header: Option<String>,
+    /// signatures spanning header and payload
+    pub header_and_payload: Option<String>,
+}
+
 #[derive(PartialEq, Debug)]
 pub struct RPMPackageMetadata {
     pub lead: Lead,
@@ -203,3 +788,111 @@ impl RPMPackageMetadata {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "signature-meta"))]
+mod test {
+    use super::*;
+
+    fn check(tag: IndexSignatureTag, res: Result<(), RPMError>) -> SignatureCheck {
+        SignatureCheck::new(tag, &[], res)
+    }
+
+    fn key_not_found() -> Result<(), RPMError> {
+        Err(RPMError::KeyNotFoundError {
+            key_ref: "24c6a8a7f4a80eb5".to_string(),
+        })
+    }
+
+    fn require_signatures(
+        signatures: Vec<SignatureCheck>,
+        payload: DigestStatus,
+    ) -> Result<(), RPMError> {
+        RPMPackage::require_signatures(VerificationReport {
+            signatures,
+            digests: DigestReport {
+                header_sha1: DigestStatus::NotPresent,
+                header_sha256: DigestStatus::Verified,
+                header_and_payload_md5: DigestStatus::NotPresent,
+                payload,
+                payload_uncompressed: DigestStatus::NotPresent,
+            },
+        })
+    }
+
+    #[test]
+    fn require_signatures_falls_back_across_tags() {
+        use IndexSignatureTag::*;
+
+        // signed twice, the verifier knows only the DSA key
+        assert!(require_signatures(
+            vec![
+                check(RPMSIGTAG_RSA, key_not_found()),
+                check(RPMSIGTAG_PGP, key_not_found()),
+                check(RPMSIGTAG_DSA, Ok(())),
+                check(RPMSIGTAG_GPG, Ok(())),
+            ],
+            DigestStatus::NotPresent
+        )
+        .is_ok());
+
+        // a bad signature is not outweighed by a good one
+        assert!(matches!(
+            require_signatures(
+                vec![
+                    check(RPMSIGTAG_RSA, Ok(())),
+                    check(RPMSIGTAG_PGP, Err(RPMError::NoSignatureFound)),
+                    check(RPMSIGTAG_GPG, Ok(())),
+                ],
+                DigestStatus::Verified
+            ),
+            Err(RPMError::NoSignatureFound)
+        ));
+
+        assert!(matches!(
+            require_signatures(
+                vec![
+                    check(RPMSIGTAG_RSA, key_not_found()),
+                    check(RPMSIGTAG_PGP, Ok(())),
+                ],
+                DigestStatus::Verified
+            ),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+        assert!(matches!(
+            require_signatures(vec![check(RPMSIGTAG_DSA, Ok(()))], DigestStatus::NotPresent),
+            Err(RPMError::TagNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn require_signatures_payload_digest() {
+        use IndexSignatureTag::*;
+
+        // the verifier knows only the new key, which signed the header only
+        let signatures = || {
+            vec![
+                check(RPMSIGTAG_RSA, key_not_found()),
+                check(RPMSIGTAG_PGP, key_not_found()),
+                check(RPMSIGTAG_OPENPGP, key_not_found()),
+                check(RPMSIGTAG_OPENPGP, Ok(())),
+            ]
+        };
+        assert!(require_signatures(signatures(), DigestStatus::Verified).is_ok());
+        assert!(matches!(
+            require_signatures(signatures(), DigestStatus::NotPresent),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+        assert!(matches!(
+            require_signatures(
+                vec![check(RPMSIGTAG_OPENPGP, Ok(()))],
+                DigestStatus::NotPresent
+            ),
+            Err(RPMError::TagNotFound(_))
+        ));
+        assert!(require_signatures(
+            vec![check(RPMSIGTAG_OPENPGP, key_not_found())],
+            DigestStatus::Verified
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Streaming reader for the cpio archive contained in the (decompressed) payload.
+//!
+//! Besides the regular `newc` (`070701`) and `crc` (`070702`) formats this
+//! also understands the stripped format (`07070X`) rpm uses for packages
+//! containing files larger than 4 GiB, where each entry only carries the
+//! index of the file within the header.
+
+use std::io::{self, Read};
+
+use crate::errors::*;
+
+const CPIO_NEWC_MAGIC: &[u8; 6] = b"070701";
+const CPIO_CRC_MAGIC: &[u8; 6] = b"070702";
+pub(crate) const CPIO_STRIPPED_MAGIC: &[u8; 6] = b"07070X";
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+/// How an archive entry refers to its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ArchiveEntryName {
+    /// Path as stored in a `newc` or `crc` entry, usually starting with `./`.
+    Path(String),
+    /// Index of the file within the header, as stored in a stripped entry.
+    Index(u32),
+}
+
+/// Walk all entries of a cpio `archive` until the trailer and call `f` with the
+/// name of each entry and a reader limited to its content.
+///
+/// Stripped entries do not record their size, it is taken from `stripped_sizes`
+/// which is indexed by the file index of the header.
+/// Content not consumed by `f` is skipped.
+pub(crate) fn for_each_entry<R, F>(
+    mut archive: R,
+    stripped_sizes: &[u64],
+    mut f: F,
+) -> Result<(), RPMError>
+where
+    R: Read,
+    F: FnMut(&ArchiveEntryName, &mut dyn Read) -> Result<(), RPMError>,
+{
+    loop {
+        let mut magic = [0u8; 6];
+        archive.read_exact(&mut magic)?;
+
+        let (name, size) = match &magic {
+            CPIO_NEWC_MAGIC | CPIO_CRC_MAGIC => {
+                let mut fields = [0u8; 13 * 8];
+                archive.read_exact(&mut fields)?;
+                let size = parse_hex_field(&fields, 6)?;
+                let name_size = parse_hex_field(&fields, 11)? as usize;
+
+                let mut name = vec![0u8; name_size];
+                archive.read_exact(&mut name)?;
+                skip(&mut archive, padding(6 + fields.len() + name_size))?;
+
+                // the name includes the terminating NUL
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+                let name = String::from_utf8_lossy(name).into_owned();
+                if name == CPIO_TRAILER {
+                    return Ok(());
+                }
+                (ArchiveEntryName::Path(name), size as u64)
+            }
+            CPIO_STRIPPED_MAGIC => {
+                let mut field = [0u8; 8];
+                archive.read_exact(&mut field)?;
+                let index = parse_hex_field(&field, 0)?;
+                skip(&mut archive, padding(6 + field.len()))?;
+
+                let size = *stripped_sizes.get(index as usize).ok_or_else(|| {
+                    RPMError::InvalidPayload(format!(
+                        "stripped cpio entry refers to file index {} but only {} files exist",
+                        index,
+                        stripped_sizes.len()
+                    ))
+                })?;
+                (ArchiveEntryName::Index(index), size)
+            }
+            _ => {
+                return Err(RPMError::InvalidPayload(format!(
+                    "unknown cpio magic {:?}",
+                    String::from_utf8_lossy(&magic)
+                )))
+            }
+        };
+
+        let mut content = (&mut archive).take(size);
+        f(&name, &mut content)?;
+        io::copy(&mut content, &mut io::sink())?;
+        if content.limit() > 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        skip(&mut archive, padding(size as usize))?;
+    }
+}
+
+fn parse_hex_field(fields: &[u8], index: usize) -> Result<u32, RPMError> {
+    let field = &fields[index * 8..(index + 1) * 8];
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|field| u32::from_str_radix(field, 16).ok())
+        .ok_or_else(|| {
+            RPMError::InvalidPayload(format!(
+                "invalid cpio header field {:?}",
+                String::from_utf8_lossy(field)
+            ))
+        })
+}
+
+/// Number of bytes needed to pad `len` to a multiple of 4.
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+fn skip<R: Read>(archive: &mut R, n: usize) -> Result<(), RPMError> {
+    let mut buf = [0u8; 4];
+    archive.read_exact(&mut buf[..n])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stripped_and_newc_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let mut archive = Vec::new();
+        archive.extend_from_slice(b"07070X00000001\0\0");
+        archive.extend_from_slice(b"hello\0\0\0");
+        let mut newc = cpio::newc::Builder::new("./foo")
+            .mode(0o100644)
+            .write(&mut archive, 3);
+        std::io::Write::write_all(&mut newc, b"bar")?;
+        newc.finish()?;
+        cpio::newc::trailer(&mut archive)?;
+
+        let mut seen = Vec::new();
+        for_each_entry(archive.as_slice(), &[0, 5], |name, content| {
+            let mut buf = Vec::new();
+            content.read_to_end(&mut buf)?;
+            seen.push((name.clone(), buf));
+            Ok(())
+        })?;
+        assert_eq!(
+            seen,
+            vec![
+                (ArchiveEntryName::Index(1), b"hello".to_vec()),
+                (ArchiveEntryName::Path("./foo".to_string()), b"bar".to_vec()),
+            ]
+        );
+
+        let err = for_each_entry(&b"07070X00000002\0\0"[..], &[0, 5], |_, _| Ok(()));
+        assert!(matches!(err, Err(RPMError::InvalidPayload(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! Signer delegating to an external command, i.e. `gpg` backed by `gpg-agent`.
+
+use super::{packet, traits};
+use crate::errors::RPMError;
+
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+
+/// Signer implementation piping the data to sign into an external command.
+///
+/// The command has to read the data from its stdin and write a single
+/// binary (not ascii armored) OpenPGP signature packet to its stdout, which
+/// is what `gpg --detach-sign` does. A non-zero exit status fails the signing.
+///
+/// Since the command holds the key, its algorithm decides
+/// the algorithm of the signature, regardless of the marker.
+#[derive(Clone, Debug)]
+pub struct CommandSigner {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl CommandSigner {
+    /// Run `program` without any arguments.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            envs: Vec::new(),
+        }
+    }
+
+    /// Sign with `gpg` using the key `key_ref`, usually a key ID or fingerprint.
+    ///
+    /// Mirrors rpm's default `%__gpg_sign_cmd`.
+    pub fn gpg(key_ref: &str) -> Self {
+        Self::new("gpg").args([
+            "--batch",
+            "--no-verbose",
+            "--no-armor",
+            "--no-secmem-warning",
+            "--digest-algo",
+            "sha256",
+            "--local-user",
+            key_ref,
+            "--detach-sign",
+        ])
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Set an environment variable for the command, i.e. `GNUPGHOME`.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+}
+
+fn sign_error(error: impl Into<Box<dyn std::error::Error>>) -> RPMError {
+    RPMError::SignError(error.into())
+}
+
+/// Read `pipe` to its end on a separate thread.
+fn drain<R: Read + Send + 'static>(mut pipe: R) -> JoinHandle<io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::with_capacity(1024);
+        pipe.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+fn join(output: JoinHandle<io::Result<Vec<u8>>>) -> Result<Vec<u8>, RPMError> {
+    output
+        .join()
+        .map_err(|_| sign_error("failed to read the output of the signing command"))?
+        .map_err(sign_error)
+}
+
+impl traits::Signing<traits::algorithm::RSA> for CommandSigner {
+    type Signature = Vec<u8>;
+
+    fn sign<R: Read>(&self, mut data: R) -> Result<Self::Signature, RPMError> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(sign_error)?;
+
+        // drain both outputs concurrently, the command may write to either one
+        // before having read all input and blocks once a pipe buffer is full
+        let stdout = drain(child.stdout.take().expect("stdout is piped"));
+        let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let written = io::copy(&mut data, &mut stdin).and_then(|_| stdin.flush());
+        // closing stdin signals the end of the data
+        drop(stdin);
+
+        let status = child.wait().map_err(sign_error)?;
+        let signature = join(stdout)?;
+        let message = join(stderr)?;
+        if !status.success() {
+            return Err(sign_error(format!(
+                "signing command {:?} failed with {}: {}",
+                self.program,
+                status,
+                String::from_utf8_lossy(&message).trim()
+            )));
+        }
+        // a command failing early may close its stdin, only relevant if it claims success
+        written.map_err(sign_error)?;
+
+        if packet::public_key_algorithm(&signature).is_none() {
+            return Err(RPMError::NoSignatureFound);
+        }
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::Signing;
+
+    #[test]
+    fn failing_command() {
+        let signer =
+            CommandSigner::new("sh").args(["-c", "cat > /dev/null; echo nope >&2; exit 3"]);
+        match signer.sign(&b"data"[..]) {
+            Err(RPMError::SignError(e)) => assert!(e.to_string().contains("nope")),
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let signer = CommandSigner::new("sh").args(["-c", "cat"]);
+        assert!(matches!(
+            signer.sign(&b"not a signature"[..]),
+            Err(RPMError::NoSignatureFound)
+        ));
+
+        // more diagnostics than fit into a pipe buffer, written before reading the data
+        let signer = CommandSigner::new("sh").args([
+            "-c",
+            "head -c 1048576 /dev/zero >&2; cat > /dev/null; exit 1",
+        ]);
+        assert!(matches!(
+            signer.sign(&b"data"[..]),
+            Err(RPMError::SignError(_))
+        ));
+
+        assert!(CommandSigner::new("/nonexistent/signer")
+            .sign(&b"data"[..])
+            .is_err());
+    }
+
+    /// Run with `cargo test -- --ignored`, requires `gpg` and `gpgconf` in the `PATH`.
+    #[cfg(feature = "signature-pgp")]
+    #[test]
+    #[ignore = "requires gpg"]
+    fn gpg_detach_sign() {
+        use crate::signature::Verifying;
+
+        // a throwaway gpg home containing only the test key
+        let tmp = tempfile::tempdir().expect("Should be able to create the gpg home");
+        let gnupg_home = tmp.path();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(gnupg_home, std::fs::Permissions::from_mode(0o700))
+                .expect("Should be able to restrict the gpg home");
+        }
+        let (signing_key, verification_key) = crate::signature::pgp::test::load_asc_keys();
+        let mut import = Command::new("gpg")
+            .env("GNUPGHOME", gnupg_home)
+            .args(["--batch", "--import"])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("gpg runs");
+        import
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(&signing_key)
+            .expect("key is written");
+        assert!(import.wait().expect("gpg finishes").success());
+
+        let signer = CommandSigner::gpg("CFD331925AB27F39").env("GNUPGHOME", gnupg_home);
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]);
+
+        let _ = Command::new("gpgconf")
+            .env("GNUPGHOME", gnupg_home)
+            .args(["--kill", "gpg-agent"])
+            .status();
+
+        let signature = signature.expect("signed");
+        let verifier = crate::signature::pgp::Verifier::load_from_asc_bytes(&verification_key)
+            .expect("PK parsing failed");
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify the signature made by gpg");
+    }
+}
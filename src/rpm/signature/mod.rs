@@ -1,9 +1,36 @@
 mod traits;
 pub use self::traits::*;
 
+pub(crate) mod packet;
+
+pub mod command;
+
 #[cfg(feature = "signature-pgp")]
 pub mod pgp;
 
+#[cfg(feature = "signature-pkcs11")]
+pub mod pkcs11;
+
+#[cfg(feature = "signature-sequoia")]
+pub mod sequoia;
+
+/// current time, truncated to the accuracy of OpenPGP timestamps
+#[cfg(any(feature = "signature-pgp", feature = "signature-sequoia"))]
+pub(crate) fn now() -> ::chrono::DateTime<::chrono::Utc> {
+    truncate_to_seconds(::chrono::offset::Utc::now())
+}
+
+#[cfg(any(feature = "signature-pgp", feature = "signature-sequoia"))]
+pub(crate) fn truncate_to_seconds(
+    time: ::chrono::DateTime<::chrono::Utc>,
+) -> ::chrono::DateTime<::chrono::Utc> {
+    // accuracy of serialized format is only down to seconds
+    use ::chrono::offset::TimeZone;
+    ::chrono::offset::Utc
+        .timestamp_opt(time.timestamp(), 0u32)
+        .unwrap()
+}
+
 /// test helper to print signatures
 pub fn echo_signature(scope: &str, signature: &[u8]) {
     log::debug!(
@@ -0,0 +1,339 @@
+//! Minimal OpenPGP signature packet inspection.
+//!
+//! Only what is needed to tell which key issued a signature,
+//! independent of the backend doing the actual verification.
+//! See [RFC 4880 section 5.2](https://tools.ietf.org/html/rfc4880#section-5.2).
+
+use std::convert::TryInto;
+
+const PACKET_TAG_SIGNATURE: u8 = 2;
+
+// public key algorithms, see RFC 4880 section 9.1 and RFC 6637
+pub(crate) const PUBKEY_ALGO_RSA: u8 = 1;
+pub(crate) const PUBKEY_ALGO_RSA_SIGN_ONLY: u8 = 3;
+
+const SUBPACKET_CREATION_TIME: u8 = 2;
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+/// Split off the first packet as its tag, its body and the remaining bytes.
+fn split_packet(packets: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&ctb, rest) = packets.split_first()?;
+    if ctb & 0x80 == 0 {
+        return None;
+    }
+    let (tag, len, rest) = if ctb & 0x40 != 0 {
+        // new format
+        let (&first, rest) = rest.split_first()?;
+        match first {
+            0..=191 => (ctb & 0x3f, first as usize, rest),
+            192..=223 => {
+                let (&second, rest) = rest.split_first()?;
+                let len = ((first as usize - 192) << 8) + second as usize + 192;
+                (ctb & 0x3f, len, rest)
+            }
+            255 => (ctb & 0x3f, be_uint(rest.get(..4)?), rest.get(4..)?),
+            // partial body lengths are not allowed for signatures
+            _ => return None,
+        }
+    } else {
+        // old format
+        let tag = (ctb >> 2) & 0x0f;
+        match ctb & 0x03 {
+            0 => (tag, be_uint(rest.get(..1)?), rest.get(1..)?),
+            1 => (tag, be_uint(rest.get(..2)?), rest.get(2..)?),
+            2 => (tag, be_uint(rest.get(..4)?), rest.get(4..)?),
+            _ => (tag, rest.len(), rest),
+        }
+    };
+    Some((tag, rest.get(..len)?, &rest[len..]))
+}
+
+/// Extract the body of the first packet, if it is a signature packet.
+fn signature_packet_body(packet: &[u8]) -> Option<&[u8]> {
+    match split_packet(packet)? {
+        (PACKET_TAG_SIGNATURE, body, _) => Some(body),
+        _ => None,
+    }
+}
+
+/// Split concatenated signature packets, as found in a detached signature.
+///
+/// Returns `None` if any of the packets is not a signature packet.
+pub(crate) fn split_signatures(mut packets: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut signatures = Vec::new();
+    while !packets.is_empty() {
+        let (tag, _, rest) = split_packet(packets)?;
+        if tag != PACKET_TAG_SIGNATURE {
+            return None;
+        }
+        signatures.push(&packets[..packets.len() - rest.len()]);
+        packets = rest;
+    }
+    Some(signatures)
+}
+
+const ARMOR_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+const ARMOR_END: &str = "-----END PGP SIGNATURE-----";
+
+/// The checksum of ASCII armor, see RFC 4880 section 6.1.
+fn crc24(data: &[u8]) -> [u8; 3] {
+    let crc = data.iter().fold(0xb7_04ceu32, |mut crc, byte| {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= 0x186_4cfb;
+            }
+        }
+        crc
+    });
+    let bytes = crc.to_be_bytes();
+    [bytes[1], bytes[2], bytes[3]]
+}
+
+/// ASCII armor the signature `packets`, the format of `.asc` files written by `gpg --armor`.
+pub(crate) fn armor(packets: &[u8]) -> String {
+    let encoded = base64::encode(packets);
+    let mut armored = String::with_capacity(encoded.len() * 65 / 64 + 128);
+    armored.push_str(ARMOR_BEGIN);
+    armored.push_str("\n\n");
+    for line in encoded.as_bytes().chunks(64) {
+        armored.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        armored.push('\n');
+    }
+    armored.push('=');
+    armored.push_str(&base64::encode(crc24(packets)));
+    armored.push('\n');
+    armored.push_str(ARMOR_END);
+    armored.push('\n');
+    armored
+}
+
+/// Decode the first ASCII armored signature within `armored`, the inverse of [`armor`].
+///
+/// Armor headers are skipped, the checksum is verified if present.
+/// Returns `None` if there is no signature or it is corrupt.
+pub(crate) fn dearmor(armored: &str) -> Option<Vec<u8>> {
+    let mut lines = armored
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != ARMOR_BEGIN);
+    lines.next()?;
+    let mut encoded = String::new();
+    let mut checksum: Option<[u8; 3]> = None;
+    // base64 never contains the colon of the armor headers
+    for line in lines.skip_while(|line| line.contains(": ") || line.is_empty()) {
+        if line == ARMOR_END {
+            let packets = base64::decode(&encoded).ok()?;
+            return match checksum {
+                Some(checksum) if checksum != crc24(&packets) => None,
+                _ => Some(packets),
+            };
+        }
+        match line.strip_prefix('=') {
+            Some(crc) => checksum = Some(base64::decode(crc).ok()?.try_into().ok()?),
+            None => encoded.push_str(line),
+        }
+    }
+    None
+}
+
+fn be_uint(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0usize, |acc, byte| (acc << 8) | *byte as usize)
+}
+
+/// Iterate over the `(type, body)` of all subpackets within `area`.
+fn subpackets(mut area: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        let (&first, rest) = area.split_first()?;
+        let (len, rest) = match first {
+            0..=191 => (first as usize, rest),
+            192..=254 => {
+                let (&second, rest) = rest.split_first()?;
+                (((first as usize - 192) << 8) + second as usize + 192, rest)
+            }
+            255 => (be_uint(rest.get(..4)?), rest.get(4..)?),
+        };
+        let subpacket = rest.get(..len)?;
+        area = &rest[len..];
+        // the highest bit flags the subpacket as critical
+        let (&typ, body) = subpacket.split_first()?;
+        Some((typ & 0x7f, body))
+    })
+}
+
+/// The fields of a signature packet identifying its issuer and algorithms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SignatureFields {
+    /// key ID as 16 lower case hex digits
+    pub(crate) key_id: Option<String>,
+    /// fingerprint as lower case hex digits, only ever present in v4 signatures
+    pub(crate) fingerprint: Option<String>,
+    pub(crate) public_key_algorithm: Option<u8>,
+    pub(crate) hash_algorithm: Option<u8>,
+    /// seconds since the epoch
+    pub(crate) creation_time: Option<u32>,
+}
+
+/// Parse the fields of the `signature`.
+///
+/// Returns `None` if the signature can not be parsed. Fields the signature
+/// does not contain, like the issuer of a v4 signature, are left empty.
+pub(crate) fn parse_signature(signature: &[u8]) -> Option<SignatureFields> {
+    let body = signature_packet_body(signature)?;
+    match body.first()? {
+        // version, hashed length, type, creation time, key id,
+        // public key algorithm, hash algorithm
+        3 => Some(SignatureFields {
+            key_id: body.get(7..15).map(hex::encode),
+            fingerprint: None,
+            public_key_algorithm: body.get(15).copied(),
+            hash_algorithm: body.get(16).copied(),
+            creation_time: body.get(3..7).map(|time| be_uint(time) as u32),
+        }),
+        // version, type, public key algorithm, hash algorithm, subpackets
+        4 => {
+            let hashed_len = be_uint(body.get(4..6)?);
+            let hashed = body.get(6..6 + hashed_len)?;
+            let rest = &body[6 + hashed_len..];
+            let unhashed_len = be_uint(rest.get(..2)?);
+            let unhashed = rest.get(2..2 + unhashed_len)?;
+
+            let all = || subpackets(hashed).chain(subpackets(unhashed));
+            // the first byte is the version of the key
+            let fingerprint = all()
+                .find(|(typ, body)| *typ == SUBPACKET_ISSUER_FINGERPRINT && body.len() > 8)
+                .map(|(_, fingerprint)| &fingerprint[1..]);
+            let key_id = all()
+                .find(|(typ, body)| *typ == SUBPACKET_ISSUER && body.len() == 8)
+                .map(|(_, key_id)| hex::encode(key_id))
+                .or_else(|| {
+                    // the key id of a v4 key are the last 8 bytes of its fingerprint
+                    fingerprint
+                        .map(|fingerprint| hex::encode(&fingerprint[fingerprint.len() - 8..]))
+                });
+            // only the hashed creation time is authenticated
+            let creation_time = subpackets(hashed)
+                .find(|(typ, body)| *typ == SUBPACKET_CREATION_TIME && body.len() == 4)
+                .map(|(_, time)| be_uint(time) as u32);
+            Some(SignatureFields {
+                key_id,
+                fingerprint: fingerprint.map(hex::encode),
+                public_key_algorithm: body.get(2).copied(),
+                hash_algorithm: body.get(3).copied(),
+                creation_time,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The key ID of the key which issued the `signature`, as 16 lower case hex digits.
+///
+/// Returns `None` if the signature can not be parsed or does not name its issuer.
+pub(crate) fn issuer_key_id(signature: &[u8]) -> Option<String> {
+    parse_signature(signature)?.key_id
+}
+
+/// The public key algorithm of the key which issued the `signature`.
+pub(crate) fn public_key_algorithm(signature: &[u8]) -> Option<u8> {
+    parse_signature(signature)?.public_key_algorithm
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inspect_v3_and_v4_signatures() {
+        // old format, two byte length, v3 by a DSA key, truncated after the hash algorithm
+        let mut v3 = vec![0x89, 0x00, 0x11, 0x03, 0x05, 0x00, 0x5b, 0xe9, 0x8c, 0x5b];
+        v3.extend_from_slice(&[0x24, 0xc6, 0xa8, 0xa7, 0xf4, 0xa8, 0x0e, 0xb5, 17, 2]);
+        assert_eq!(Some("24c6a8a7f4a80eb5".to_string()), issuer_key_id(&v3));
+        assert_eq!(Some(17), public_key_algorithm(&v3));
+
+        // new format, v4, only an issuer fingerprint in the hashed area
+        let mut fingerprint = vec![22, SUBPACKET_ISSUER_FINGERPRINT, 4];
+        fingerprint.extend_from_slice(&[0u8; 12]);
+        fingerprint.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut body = vec![0x04, 0x00, 0x01, 0x08, 0x00, fingerprint.len() as u8];
+        body.extend_from_slice(&fingerprint);
+        body.extend_from_slice(&[0x00, 0x00]);
+        let mut v4 = vec![0xc2, body.len() as u8];
+        v4.extend_from_slice(&body);
+        assert_eq!(Some("0102030405060708".to_string()), issuer_key_id(&v4));
+        assert_eq!(Some(1), public_key_algorithm(&v4));
+
+        assert_eq!(None, issuer_key_id(&[0x99, 0x00, 0x01, 0x04]));
+    }
+
+    #[test]
+    fn parse_v3_and_v4_signatures() {
+        let mut v3 = vec![0x89, 0x00, 0x11, 0x03, 0x05, 0x00, 0x5b, 0xe9, 0x8c, 0x5b];
+        v3.extend_from_slice(&[0x24, 0xc6, 0xa8, 0xa7, 0xf4, 0xa8, 0x0e, 0xb5, 17, 2]);
+        assert_eq!(
+            Some(SignatureFields {
+                key_id: Some("24c6a8a7f4a80eb5".to_string()),
+                fingerprint: None,
+                public_key_algorithm: Some(17),
+                hash_algorithm: Some(2),
+                creation_time: Some(0x5be98c5b),
+            }),
+            parse_signature(&v3)
+        );
+
+        // the creation time is hashed, the issuer is not
+        let mut hashed = vec![5, SUBPACKET_CREATION_TIME, 0x60, 0x00, 0x00, 0x01];
+        hashed.extend_from_slice(&[22, SUBPACKET_ISSUER_FINGERPRINT, 4]);
+        hashed.extend_from_slice(&[0xaa; 12]);
+        hashed.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let unhashed = [9, SUBPACKET_ISSUER, 8, 7, 6, 5, 4, 3, 2, 1];
+        let mut body = vec![0x04, 0x00, 22, 10, 0x00, hashed.len() as u8];
+        body.extend_from_slice(&hashed);
+        body.extend_from_slice(&[0x00, unhashed.len() as u8]);
+        body.extend_from_slice(&unhashed);
+        let mut v4 = vec![0xc2, body.len() as u8];
+        v4.extend_from_slice(&body);
+        assert_eq!(
+            Some(SignatureFields {
+                key_id: Some("0807060504030201".to_string()),
+                fingerprint: Some(format!("{}0102030405060708", "aa".repeat(12))),
+                public_key_algorithm: Some(22),
+                hash_algorithm: Some(10),
+                creation_time: Some(0x60000001),
+            }),
+            parse_signature(&v4)
+        );
+    }
+
+    #[test]
+    fn armor_and_split_signatures() {
+        let mut v3 = vec![0x89, 0x00, 0x11, 0x03, 0x05, 0x00, 0x5b, 0xe9, 0x8c, 0x5b];
+        v3.extend_from_slice(&[0x24, 0xc6, 0xa8, 0xa7, 0xf4, 0xa8, 0x0e, 0xb5, 17, 2]);
+        let v4 = [0xc2, 0x04, 0x04, 0x00, 0x01, 0x08];
+        let packets = [&v3[..], &v4[..]].concat();
+        assert_eq!(Some(vec![&v3[..], &v4[..]]), split_signatures(&packets));
+        assert_eq!(None, split_signatures(&[0x99, 0x00, 0x01, 0x04]));
+        assert_eq!(None, split_signatures(&packets[..packets.len() - 1]));
+
+        let armored = armor(&packets);
+        assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----\n\n"));
+        assert!(armored.ends_with("\n-----END PGP SIGNATURE-----\n"));
+        assert_eq!(Some(packets.clone()), dearmor(&armored));
+
+        // as written by gpg, with armor headers and surrounding text
+        let with_headers = format!(
+            "some text\r\n{}",
+            armored.replacen("\n\n", "\r\nComment: some comment\r\n\r\n", 1)
+        );
+        assert_eq!(Some(packets.clone()), dearmor(&with_headers));
+
+        let checksum = armored.rfind("\n=").unwrap() + 2;
+        let corrupt = format!("{}AAAA{}", &armored[..checksum], &armored[checksum + 4..]);
+        assert_eq!(None, dearmor(&corrupt));
+        assert_eq!(None, dearmor("no signature"));
+    }
+}
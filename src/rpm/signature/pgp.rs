@@ -1,65 +1,187 @@
 use super::traits;
+use super::{now, truncate_to_seconds};
 use crate::errors::RPMError;
 
 use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
 
-use ::pgp::{composed::Deserializable, types::KeyTrait};
+use ::pgp::{
+    composed::Deserializable,
+    types::{KeyTrait, SecretKeyTrait},
+};
 
 use ::pgp::packet::*;
 
-fn now() -> ::chrono::DateTime<::chrono::Utc> {
-    // accuracy of serialized format is only down to seconds
-    use ::chrono::offset::TimeZone;
-    let now = ::chrono::offset::Utc::now();
-    ::chrono::offset::Utc.timestamp(now.timestamp(), 0u32)
+/// Configuration of a binary document signature, as issued by all signers.
+pub(crate) fn signature_config(
+    issuer: ::pgp::types::KeyId,
+    pub_alg: ::pgp::crypto::public_key::PublicKeyAlgorithm,
+    hash_alg: ::pgp::crypto::hash::HashAlgorithm,
+    created: ::chrono::DateTime<::chrono::Utc>,
+    signers_user_id: Option<String>,
+) -> SignatureConfig {
+    let mut hashed_subpackets = vec![
+        Subpacket::SignatureCreationTime(created),
+        Subpacket::Issuer(issuer.clone()),
+    ];
+    if let Some(user_id) = signers_user_id {
+        hashed_subpackets.push(Subpacket::SignersUserID(user_id));
+    }
+
+    SignatureConfig {
+        version: SignatureVersion::V4,
+        typ: SignatureType::Binary,
+        pub_alg,
+        hash_alg,
+        issuer: Some(issuer),
+        created: Some(created),
+        unhashed_subpackets: vec![],
+        hashed_subpackets,
+    }
+}
+
+pub(crate) fn serialize_signature(signature_packet: &Signature) -> Result<Vec<u8>, RPMError> {
+    let mut signature_bytes = Vec::with_capacity(1024);
+    let mut cursor = Cursor::new(&mut signature_bytes);
+    ::pgp::packet::write_packet(&mut cursor, signature_packet)
+        .map_err(|e| RPMError::SignError(Box::new(e)))?;
+    Ok(signature_bytes)
 }
 
 /// Signer implementation using the `pgp` crate.
 ///
-/// Note that this only supports ascii armored key files
-/// commonly with the file extension `.asc` as generated
-/// by i.e. `gpg`.
-#[derive(Clone, Debug)]
-pub struct Signer {
+/// Keys can be loaded ascii armored, commonly with the file extension
+/// `.asc`, or binary, as exported by `gpg --export-secret-keys`.
+/// Passphrase protected keys need the passphrase set via
+/// [`with_key_passphrase`](Self::with_key_passphrase)
+/// or [`with_key_passphrase_fn`](Self::with_key_passphrase_fn).
+///
+/// Signatures use SHA2-256 and the current time unless configured otherwise.
+/// With a fixed [`creation time`](Self::with_creation_time) signing the same
+/// data with the same key yields identical signatures.
+///
+/// The algorithm marker `A` has to match the signing key, [`RSA`](traits::algorithm::RSA)
+/// as loaded or [`EdDSA`](traits::algorithm::EdDSA) for Ed25519 keys, see
+/// [`with_algorithm`](Self::with_algorithm). The `pgp` crate can not sign with ECDSA keys,
+/// signing fails with [`RPMError::UnsupportedPublicKeyAlgorithm`] for those.
+#[derive(Clone)]
+pub struct Signer<A = traits::algorithm::RSA> {
     secret_key: ::pgp::composed::signed_key::SignedSecretKey,
+    key_passphrase: Arc<dyn Fn() -> String + Send + Sync>,
+    hash_algorithm: ::pgp::crypto::hash::HashAlgorithm,
+    creation_time: Option<::chrono::DateTime<::chrono::Utc>>,
+    /// index into the secret subkeys, the primary key is used if `None`
+    signing_subkey: Option<usize>,
+    signers_user_id: Option<String>,
+    algorithm: std::marker::PhantomData<A>,
 }
 
-impl traits::Signing<traits::algorithm::RSA> for Signer {
+impl<A> std::fmt::Debug for Signer<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the passphrase
+        f.debug_struct("Signer")
+            .field("secret_key", &self.secret_key)
+            .field("hash_algorithm", &self.hash_algorithm)
+            .field("creation_time", &self.creation_time)
+            .field("signing_subkey", &self.signing_subkey)
+            .field("signers_user_id", &self.signers_user_id)
+            .finish()
+    }
+}
+
+impl traits::Signing<traits::algorithm::RSA> for Signer<traits::algorithm::RSA> {
     type Signature = Vec<u8>;
 
     /// Despite the fact the API suggest zero copy pattern,
     /// it internally creates a copy until crate `pgp` provides
     /// a `Read` based implementation.
     fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
-        let passwd_fn = || String::new();
+        use ::pgp::crypto::public_key::PublicKeyAlgorithm;
+        self.sign_checked(
+            &[PublicKeyAlgorithm::RSA, PublicKeyAlgorithm::RSASign],
+            data,
+        )
+    }
+}
 
-        let now = now();
+impl traits::Signing<traits::algorithm::EdDSA> for Signer<traits::algorithm::EdDSA> {
+    type Signature = Vec<u8>;
 
-        let sig_cfg = SignatureConfig {
-            version: SignatureVersion::V4,
-            typ: SignatureType::Binary,
-            pub_alg: ::pgp::crypto::public_key::PublicKeyAlgorithm::RSA,
-            hash_alg: ::pgp::crypto::hash::HashAlgorithm::SHA2_256,
-            issuer: Some(self.secret_key.key_id()),
-            created: Some(now),
-            unhashed_subpackets: vec![],
-            hashed_subpackets: vec![
-                Subpacket::SignatureCreationTime(now),
-                Subpacket::Issuer(self.secret_key.key_id()),
-                //::pgp::packet::Subpacket::SignersUserID("rpm"), TODO this would be a nice addition
-            ],
+    fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+        self.sign_checked(
+            &[::pgp::crypto::public_key::PublicKeyAlgorithm::EdDSA],
+            data,
+        )
+    }
+}
+
+impl<A> Signer<A> {
+    /// Sign with the selected key, if its algorithm is one of `expected`.
+    fn sign_checked<R: Read>(
+        &self,
+        expected: &[::pgp::crypto::public_key::PublicKeyAlgorithm],
+        data: R,
+    ) -> Result<Vec<u8>, RPMError> {
+        use ::pgp::crypto::public_key::PublicKeyAlgorithm;
+        let algorithm = match self.signing_subkey {
+            Some(index) => self.secret_key.secret_subkeys[index].algorithm(),
+            None => self.secret_key.algorithm(),
         };
+        if !expected.contains(&algorithm) {
+            let reason = match algorithm {
+                PublicKeyAlgorithm::RSA
+                | PublicKeyAlgorithm::RSASign
+                | PublicKeyAlgorithm::EdDSA => {
+                    "it does not match the algorithm of the signer, see `Signer::with_algorithm`"
+                }
+                _ => "the `pgp` crate can not sign with it",
+            };
+            return Err(RPMError::UnsupportedPublicKeyAlgorithm {
+                algorithm: format!("{:?}", algorithm),
+                reason,
+            });
+        }
+        match self.signing_subkey {
+            Some(index) => self.sign_with(&self.secret_key.secret_subkeys[index], data),
+            None => self.sign_with(&self.secret_key, data),
+        }
+    }
+
+    fn sign_with<K, R>(&self, key: &K, data: R) -> Result<Vec<u8>, RPMError>
+    where
+        K: SecretKeyTrait + KeyTrait,
+        R: Read,
+    {
+        let passwd_fn = || (self.key_passphrase)();
+
+        let sig_cfg = signature_config(
+            key.key_id(),
+            key.algorithm(),
+            self.hash_algorithm,
+            self.creation_time.unwrap_or_else(now),
+            self.signers_user_id.clone(),
+        );
 
         let signature_packet = sig_cfg
-            .sign(&self.secret_key, passwd_fn, data)
+            .sign(key, passwd_fn, data)
             .map_err(|e| RPMError::SignError(Box::new(e)))?;
 
-        let mut signature_bytes = Vec::with_capacity(1024);
-        let mut cursor = Cursor::new(&mut signature_bytes);
-        ::pgp::packet::write_packet(&mut cursor, &signature_packet)
-            .map_err(|e| RPMError::SignError(Box::new(e)))?;
+        serialize_signature(&signature_packet)
+    }
 
-        Ok(signature_bytes)
+    /// Use the signer for a key of algorithm `B`, i.e. [`EdDSA`](traits::algorithm::EdDSA)
+    /// for an Ed25519 key.
+    pub fn with_algorithm<B>(self) -> Signer<B> {
+        Signer {
+            secret_key: self.secret_key,
+            key_passphrase: self.key_passphrase,
+            hash_algorithm: self.hash_algorithm,
+            creation_time: self.creation_time,
+            signing_subkey: self.signing_subkey,
+            signers_user_id: self.signers_user_id,
+            algorithm: std::marker::PhantomData,
+        }
     }
 }
 
@@ -80,15 +202,167 @@ impl Signer {
                 source: Box::new(e),
                 details: "Failed to parse bytes as ascii armored key",
             })?;
-        Ok(Self { secret_key })
+        Ok(Self::new(secret_key))
+    }
+
+    /// load the private key for signing from its binary representation
+    pub fn load_from_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let secret_key =
+            ::pgp::composed::signed_key::SignedSecretKey::from_bytes(input).map_err(|e| {
+                RPMError::KeyLoadError {
+                    source: Box::new(e),
+                    details: "Failed to parse bytes as binary key",
+                }
+            })?;
+        Ok(Self::new(secret_key))
+    }
+
+    fn new(secret_key: ::pgp::composed::signed_key::SignedSecretKey) -> Self {
+        Self {
+            secret_key,
+            key_passphrase: Arc::new(String::new),
+            hash_algorithm: ::pgp::crypto::hash::HashAlgorithm::SHA2_256,
+            creation_time: None,
+            signing_subkey: None,
+            signers_user_id: None,
+            algorithm: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> Signer<A> {
+    /// Sign with the key identified by `key_ref`, either its key ID or its fingerprint
+    /// as hex digits, optionally prefixed by `0x`.
+    ///
+    /// The primary key may be selected as well.
+    pub fn with_signing_key(mut self, key_ref: &str) -> Result<Self, RPMError> {
+        let key_ref = key_ref.trim_start_matches("0x").to_lowercase();
+        let matches = |key: &dyn KeyTrait| {
+            hex::encode(key.key_id()) == key_ref || hex::encode(key.fingerprint()) == key_ref
+        };
+        if matches(&self.secret_key) {
+            self.signing_subkey = None;
+        } else {
+            let index = self
+                .secret_key
+                .secret_subkeys
+                .iter()
+                .position(|sub_key| matches(sub_key))
+                .ok_or(RPMError::KeyNotFoundError { key_ref })?;
+            self.signing_subkey = Some(index);
+        }
+        Ok(self)
+    }
+
+    /// Sign with the most recently created subkey whose binding signature grants
+    /// at least the capabilities in `flags`, usually [`KeyFlags::sign`].
+    ///
+    /// Revoked subkeys are skipped, as are subkeys not valid at the
+    /// [`creation time`](Self::with_creation_time) set so far or, without one, now.
+    pub fn with_signing_subkey_by_flags(mut self, flags: KeyFlags) -> Result<Self, RPMError> {
+        let wanted = flags.0;
+        let at = self.creation_time.unwrap_or_else(now);
+        let index = self
+            .secret_key
+            .secret_subkeys
+            .iter()
+            .enumerate()
+            .filter(|(_, sub_key)| {
+                let created = *sub_key.key.created_at();
+                let revoked = sub_key
+                    .signatures
+                    .iter()
+                    .any(|signature| signature.typ() == SignatureType::SubkeyRevocation);
+                // the most recent binding signature is in effect
+                let binding = sub_key
+                    .signatures
+                    .iter()
+                    .filter(|signature| signature.typ() == SignatureType::SubkeyBinding)
+                    .max_by_key(|signature| signature.created().copied());
+                let binding = match binding {
+                    Some(binding) if !revoked && created <= at => binding,
+                    _ => return false,
+                };
+                // `pgp` parses the expiration, seconds after the key creation, as timestamp
+                let expired = match binding.key_expiration_time().map(|time| time.timestamp()) {
+                    Some(seconds) if seconds > 0 => {
+                        created + ::chrono::Duration::seconds(seconds) <= at
+                    }
+                    _ => false,
+                };
+                !expired && binding.key_flags().0 & wanted == wanted
+            })
+            .max_by_key(|(_, sub_key)| *sub_key.key.created_at())
+            .map(|(index, _)| index)
+            .ok_or_else(|| RPMError::KeyNotFoundError {
+                key_ref: format!("subkey with flags {:?}", flags),
+            })?;
+        self.signing_subkey = Some(index);
+        Ok(self)
+    }
+
+    /// Add a `SignersUserID` subpacket naming the signing identity,
+    /// i.e. `Release Engineering <release@example.com>`.
+    pub fn with_signers_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.signers_user_id = Some(user_id.into());
+        self
+    }
+
+    /// Hash the signed data with `hash_algorithm`, i.e. `SHA2_384` or `SHA2_512`, instead of `SHA2_256`.
+    ///
+    /// Fails for anything but the SHA2 family, rpm rejects `MD5` and `SHA1` for new signatures.
+    pub fn with_hash_algorithm(
+        mut self,
+        hash_algorithm: ::pgp::crypto::hash::HashAlgorithm,
+    ) -> Result<Self, RPMError> {
+        use ::pgp::crypto::hash::HashAlgorithm;
+        match hash_algorithm {
+            HashAlgorithm::SHA2_224
+            | HashAlgorithm::SHA2_256
+            | HashAlgorithm::SHA2_384
+            | HashAlgorithm::SHA2_512 => {
+                self.hash_algorithm = hash_algorithm;
+                Ok(self)
+            }
+            hash_algorithm => Err(RPMError::UnsupportedHashAlgorithm(format!(
+                "{:?}",
+                hash_algorithm
+            ))),
+        }
+    }
+
+    /// Stamp all signatures with `creation_time` instead of the current time,
+    /// i.e. `SOURCE_DATE_EPOCH` for reproducible builds.
+    ///
+    /// Only whole seconds are stored.
+    pub fn with_creation_time(mut self, creation_time: ::chrono::DateTime<::chrono::Utc>) -> Self {
+        self.creation_time = Some(truncate_to_seconds(creation_time));
+        self
+    }
+
+    /// Use `passphrase` to unlock a protected key when signing.
+    pub fn with_key_passphrase(self, passphrase: impl Into<String>) -> Self {
+        let passphrase = passphrase.into();
+        self.with_key_passphrase_fn(move || passphrase.clone())
+    }
+
+    /// Call `passphrase_fn` to obtain the passphrase whenever the protected key has to be unlocked,
+    /// i.e. to query an agent or a secret store instead of keeping it in memory.
+    pub fn with_key_passphrase_fn(
+        mut self,
+        passphrase_fn: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.key_passphrase = Arc::new(passphrase_fn);
+        self
     }
 }
 
 /// Verifier implementation using the `pgp` crate.
 ///
-/// Note that this only supports ascii armored key files
-/// commonly with the file extension `.asc` as generated
-/// by i.e. `gpg`.
+/// Keys can be loaded ascii armored, commonly with the file extension
+/// `.asc`, or binary, as exported by `gpg --export`.
+///
+/// Like the [`Signer`], the algorithm is taken from the loaded key.
 #[derive(Clone, Debug)]
 pub struct Verifier {
     public_key: ::pgp::composed::signed_key::SignedPublicKey,
@@ -114,79 +388,158 @@ impl traits::Verifying<traits::algorithm::RSA> for Verifier {
     /// Despite the fact the API suggest zero copy pattern,
     /// it internally creates a copy until crate `pgp` provides
     /// a `Read` based implementation.
-    fn verify<R: Read>(&self, mut data: R, signature: &[u8]) -> Result<(), RPMError> {
+    fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError> {
         let signature = Self::parse_signature(signature)?;
+        verify_with_key(&self.public_key, &signature, data)
+    }
+}
 
-        log::debug!("Signature issued by: {:?}", signature.issuer());
+/// Verify `signature` using the primary key or the subkey referenced as issuer.
+fn verify_with_key<R: Read>(
+    public_key: &::pgp::composed::signed_key::SignedPublicKey,
+    signature: &::pgp::packet::Signature,
+    mut data: R,
+) -> Result<(), RPMError> {
+    log::debug!("Signature issued by: {:?}", signature.issuer());
 
-        if let Some(key_id) = signature.issuer() {
-            log::trace!("Signature has issuer ref: {:?}", key_id);
+    if let Some(key_id) = signature.issuer() {
+        log::trace!("Signature has issuer ref: {:?}", key_id);
 
-            if self.public_key.key_id() == *key_id {
-                return signature.verify(&self.public_key, data).map_err(|e| {
-                    RPMError::VerificationError {
-                        source: Box::new(e),
-                        key_ref: format!("{:?}", key_id),
-                    }
-                });
-            } else {
-                log::trace!(
-                    "Signature issuer key id {:?} does not match primary keys key id: {:?}",
-                    key_id,
-                    self.public_key.key_id()
-                );
-            }
-
-            self.public_key
-                .public_subkeys
-                .iter()
-                .filter_map(|sub_key| {
-                    if sub_key.key_id().as_ref() == key_id.as_ref() {
-                        log::trace!(
-                            "Found a matching key id {:?} == {:?}",
-                            sub_key.key_id(),
-                            key_id
-                        );
-                        Some(sub_key)
-                    } else {
-                        log::trace!("Not the one we want: {:?}", sub_key);
-                        None
-                    }
-                })
-                .fold(
-                    Err(RPMError::KeyNotFoundError {
-                        key_ref: format!("{:?}", key_id),
-                    }),
-                    |previous_res, sub_key| {
-                        if previous_res.is_err() {
-                            log::trace!("Test next candidate subkey");
-                            signature.verify(sub_key, &mut data).map_err(|e| {
-                                RPMError::VerificationError {
-                                    source: Box::new(e),
-                                    key_ref: format!("{:?}", sub_key.key_id()),
-                                }
-                            })
-                        } else {
-                            log::trace!("Signature already verified, nop");
-                            Ok(())
-                        }
-                    },
-                )
+        if public_key.key_id() == *key_id {
+            return verify_by(
+                signature,
+                public_key,
+                public_key.primary_key.public_params(),
+                data,
+            );
         } else {
             log::trace!(
-                "Signature has no issuer ref, attempting primary key: {:?}",
-                self.public_key.primary_key.key_id()
+                "Signature issuer key id {:?} does not match primary keys key id: {:?}",
+                key_id,
+                public_key.key_id()
             );
-            signature
-                .verify(&self.public_key, data)
-                .map_err(|e| RPMError::VerificationError {
-                    source: Box::new(e),
-                    key_ref: format!("{:?}", self.public_key.key_id()),
-                })
         }
+
+        public_key
+            .public_subkeys
+            .iter()
+            .filter_map(|sub_key| {
+                if sub_key.key_id().as_ref() == key_id.as_ref() {
+                    log::trace!(
+                        "Found a matching key id {:?} == {:?}",
+                        sub_key.key_id(),
+                        key_id
+                    );
+                    Some(sub_key)
+                } else {
+                    log::trace!("Not the one we want: {:?}", sub_key);
+                    None
+                }
+            })
+            .fold(
+                Err(RPMError::KeyNotFoundError {
+                    key_ref: format!("{:?}", key_id),
+                }),
+                |previous_res, sub_key| {
+                    if previous_res.is_err() {
+                        log::trace!("Test next candidate subkey");
+                        verify_by(signature, sub_key, sub_key.key.public_params(), &mut data)
+                    } else {
+                        log::trace!("Signature already verified, nop");
+                        Ok(())
+                    }
+                },
+            )
+    } else {
+        log::trace!(
+            "Signature has no issuer ref, attempting primary key: {:?}",
+            public_key.primary_key.key_id()
+        );
+        verify_by(
+            signature,
+            public_key,
+            public_key.primary_key.public_params(),
+            data,
+        )
+    }
+}
+
+/// Verify `signature` using `key`, whose parameters are `params`.
+///
+/// `pgp` parses DSA keys and signatures but does not verify them,
+/// which is done here for the sake of packages from before RSA became common.
+fn verify_by<K: ::pgp::types::PublicKeyTrait, R: Read>(
+    signature: &::pgp::packet::Signature,
+    key: &K,
+    params: &::pgp::types::PublicParams,
+    data: R,
+) -> Result<(), RPMError> {
+    let verification_error = |source: Box<dyn std::error::Error>| RPMError::VerificationError {
+        source,
+        key_ref: format!("{:?}", key.key_id()),
+    };
+    let (p, q, g, y) = match params {
+        ::pgp::types::PublicParams::DSA { p, q, g, y } => (p, q, g, y),
+        _ => {
+            return signature
+                .verify(key, data)
+                .map_err(|e| verification_error(Box::new(e)))
+        }
+    };
+
+    // same as `Signature::verify`
+    let config = &signature.config;
+    let hash = config
+        .hash_alg
+        .new_hasher()
+        .and_then(|mut hasher| {
+            config.hash_data_to_sign(&mut *hasher, data)?;
+            let len = config.hash_signature_data(&mut *hasher)?;
+            hasher.update(&config.trailer(len));
+            Ok(hasher.finish())
+        })
+        .map_err(|e| verification_error(Box::new(e)))?;
+    if hash.get(..2) != Some(&signature.signed_hash_value[..]) {
+        return Err(verification_error("invalid signed hash value".into()));
+    }
+    match signature.signature.as_slice() {
+        [r, s] if dsa_verify(p, q, g, y, &hash, r, s) => Ok(()),
+        _ => Err(verification_error("invalid DSA signature".into())),
     }
 }
 
+/// Check the DSA signature `(r, s)` of `hash`, see FIPS 186-4 section 4.7.
+fn dsa_verify(
+    p: &::pgp::types::Mpi,
+    q: &::pgp::types::Mpi,
+    g: &::pgp::types::Mpi,
+    y: &::pgp::types::Mpi,
+    hash: &[u8],
+    r: &::pgp::types::Mpi,
+    s: &::pgp::types::Mpi,
+) -> bool {
+    use num_bigint_dig::BigUint;
+    use num_traits::Zero;
+
+    let uint = |mpi: &::pgp::types::Mpi| BigUint::from_bytes_be(mpi.as_bytes());
+    let (p, q, g, y, r, s) = (uint(p), uint(q), uint(g), uint(y), uint(r), uint(s));
+    if q.is_zero() || p.is_zero() || r.is_zero() || s.is_zero() || r >= q || s >= q {
+        return false;
+    }
+    // the leftmost bits of the hash, as many as `q` has
+    let mut z = BigUint::from_bytes_be(hash);
+    let hash_bits = hash.len() * 8;
+    if hash_bits > q.bits() {
+        z >>= hash_bits - q.bits();
+    }
+    // `q` is prime, so this is the inverse of `s`
+    let w = s.modpow(&(&q - 2u32), &q);
+    let u1 = (z * &w) % &q;
+    let u2 = (&r * &w) % &q;
+    let v = (g.modpow(&u1, &p) * y.modpow(&u2, &p)) % &p % &q;
+    v == r
+}
+
 impl Verifier {
     pub fn load_from_asc_bytes(input: &[u8]) -> Result<Self, RPMError> {
         // only asc loading is supported right now
@@ -197,6 +550,9 @@ impl Verifier {
         Self::load_from_asc(input)
     }
 
+    /// Load an ascii armored public key.
+    ///
+    /// Only the first key of `input` is used, load a [`Keyring`] to verify with all of them.
     pub fn load_from_asc(input: &str) -> Result<Self, RPMError> {
         let (public_key, _) = ::pgp::composed::signed_key::SignedPublicKey::from_string(input)
             .map_err(|e| RPMError::KeyLoadError {
@@ -206,6 +562,197 @@ impl Verifier {
 
         Ok(Self { public_key })
     }
+
+    /// load the public key from its binary representation
+    ///
+    /// Like [`load_from_asc`](Self::load_from_asc), only the first key is used.
+    pub fn load_from_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let public_key =
+            ::pgp::composed::signed_key::SignedPublicKey::from_bytes(input).map_err(|e| {
+                RPMError::KeyLoadError {
+                    source: Box::new(e),
+                    details: "Failed to parse bytes as binary key",
+                }
+            })?;
+        Ok(Self { public_key })
+    }
+}
+
+/// Verifier implementation holding any number of public keys,
+/// using the one matching the issuer of a signature.
+///
+/// Like [`Verifier`] this supports ascii armored and binary keys,
+/// but a single file may contain multiple keys or armored blocks.
+#[derive(Clone, Debug, Default)]
+pub struct Keyring {
+    public_keys: Vec<::pgp::composed::signed_key::SignedPublicKey>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_from_asc_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let mut keyring = Self::new();
+        keyring.add_asc_bytes(input)?;
+        Ok(keyring)
+    }
+
+    pub fn load_from_asc(input: &str) -> Result<Self, RPMError> {
+        let mut keyring = Self::new();
+        keyring.add_asc(input)?;
+        Ok(keyring)
+    }
+
+    /// Load all files named `RPM-GPG-KEY-*` within `dir`, as found in `/etc/pki/rpm-gpg`.
+    pub fn load_from_dir(dir: impl AsRef<Path>) -> Result<Self, RPMError> {
+        let mut keyring = Self::new();
+        keyring.add_dir(dir)?;
+        Ok(keyring)
+    }
+
+    pub fn add_asc_bytes(&mut self, input: &[u8]) -> Result<(), RPMError> {
+        let input = ::std::str::from_utf8(input).map_err(|e| RPMError::KeyLoadError {
+            source: Box::new(e),
+            details: "Failed to parse bytes as utf8 for ascii armored parsing",
+        })?;
+        self.add_asc(input)
+    }
+
+    /// Add all keys of all armored blocks within `input`.
+    pub fn add_asc(&mut self, input: &str) -> Result<(), RPMError> {
+        let starts = input
+            .match_indices("-----BEGIN PGP")
+            .map(|(start, _)| start)
+            .collect::<Vec<_>>();
+        if starts.is_empty() {
+            return Err(RPMError::KeyLoadError {
+                source: Box::new(::pgp::errors::Error::NoMatchingPacket),
+                details: "Failed to find an ascii armored block",
+            });
+        }
+        let ends = starts.iter().skip(1).copied().chain(Some(input.len()));
+        for (start, end) in starts.iter().copied().zip(ends) {
+            let (public_keys, _) =
+                ::pgp::composed::signed_key::SignedPublicKey::from_string_many(&input[start..end])
+                    .map_err(|e| RPMError::KeyLoadError {
+                        source: Box::new(e),
+                        details: "Failed to parse bytes as ascii armored key",
+                    })?;
+            for public_key in public_keys {
+                self.public_keys
+                    .push(public_key.map_err(|e| RPMError::KeyLoadError {
+                        source: Box::new(e),
+                        details: "Failed to parse bytes as ascii armored key",
+                    })?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add all keys of the binary `input`, i.e. an export of `gpg --export`.
+    pub fn add_bytes(&mut self, input: &[u8]) -> Result<(), RPMError> {
+        let mut added = 0;
+        for public_key in ::pgp::composed::signed_key::SignedPublicKey::from_bytes_many(input) {
+            self.public_keys
+                .push(public_key.map_err(|e| RPMError::KeyLoadError {
+                    source: Box::new(e),
+                    details: "Failed to parse bytes as binary key",
+                })?);
+            added += 1;
+        }
+        if added == 0 {
+            return Err(RPMError::KeyLoadError {
+                source: Box::new(::pgp::errors::Error::NoMatchingPacket),
+                details: "Failed to find a binary key",
+            });
+        }
+        Ok(())
+    }
+
+    /// Add all keys of the file at `path`, either ascii armored or binary.
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<(), RPMError> {
+        let input = std::fs::read(path)?;
+        let is_armored = input.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'-');
+        if is_armored {
+            self.add_asc_bytes(&input)
+        } else {
+            self.add_bytes(&input)
+        }
+    }
+
+    /// Add all files named `RPM-GPG-KEY-*` within `dir`.
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> Result<(), RPMError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("RPM-GPG-KEY-")
+                && entry.file_type()?.is_file()
+            {
+                paths.push(entry.path());
+            }
+        }
+        // deterministic order, independent of the file system
+        paths.sort();
+        for path in paths {
+            self.add_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Number of primary keys.
+    pub fn len(&self) -> usize {
+        self.public_keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.public_keys.is_empty()
+    }
+}
+
+impl traits::Verifying<traits::algorithm::RSA> for Keyring {
+    type Signature = Vec<u8>;
+
+    fn verify<R: Read>(&self, data: R, signature: &[u8]) -> Result<(), RPMError> {
+        let signature = Verifier::parse_signature(signature)?;
+        match signature.issuer() {
+            Some(key_id) => {
+                let public_key = self
+                    .public_keys
+                    .iter()
+                    .find(|public_key| {
+                        public_key.key_id() == *key_id
+                            || public_key
+                                .public_subkeys
+                                .iter()
+                                .any(|sub_key| sub_key.key_id() == *key_id)
+                    })
+                    .ok_or_else(|| RPMError::KeyNotFoundError {
+                        key_ref: format!("{:?}", key_id),
+                    })?;
+                verify_with_key(public_key, &signature, data)
+            }
+            None => {
+                // without issuer, the data has to be buffered to try every key
+                let mut buffer = Vec::new();
+                let mut data = data;
+                data.read_to_end(&mut buffer)?;
+                self.public_keys
+                    .iter()
+                    .map(|public_key| verify_with_key(public_key, &signature, buffer.as_slice()))
+                    .find(|res| res.is_ok())
+                    .unwrap_or_else(|| {
+                        Err(RPMError::KeyNotFoundError {
+                            key_ref: "signature without issuer".to_string(),
+                        })
+                    })
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -234,6 +781,337 @@ pub(crate) mod test {
         (signing_key.to_vec(), verification_key.to_vec())
     }
 
+    /// Load a second pair of sample keys, from a different vendor.
+    pub(crate) fn load_second_asc_keys() -> (Vec<u8>, Vec<u8>) {
+        let signing_key = include_bytes!("../../../test_assets/second_secret_key.asc");
+        let verification_key = include_bytes!("../../../test_assets/second_public_key.asc");
+        (signing_key.to_vec(), verification_key.to_vec())
+    }
+
+    #[test]
+    fn keyring_matches_issuer() {
+        let _ = env_logger::try_init();
+        let (signer, verifier) = prep();
+        let (second_signing_key, second_verification_key) = load_second_asc_keys();
+        let second_signer =
+            Signer::load_from_asc_bytes(second_signing_key.as_slice()).expect("PK parsing failed");
+
+        // concatenated armored blocks
+        let mut armored = load_asc_keys().1;
+        armored.extend_from_slice(&second_verification_key);
+        let keyring = Keyring::load_from_asc_bytes(armored.as_slice()).expect("keyring");
+        assert_eq!(keyring.len(), 2);
+        // a verifier takes the first key only
+        let first_only = Verifier::load_from_asc_bytes(armored.as_slice()).expect("first key");
+
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("signed");
+        let second_signature = second_signer.sign(&data[..]).expect("signed");
+
+        keyring
+            .verify(&data[..], signature.as_slice())
+            .expect("first key is part of the keyring");
+        keyring
+            .verify(&data[..], second_signature.as_slice())
+            .expect("second key is part of the keyring");
+        assert!(keyring.verify(&b"other"[..], signature.as_slice()).is_err());
+        assert!(matches!(
+            verifier.verify(&data[..], second_signature.as_slice()),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+        first_only
+            .verify(&data[..], signature.as_slice())
+            .expect("first key is loaded");
+        assert!(first_only
+            .verify(&data[..], second_signature.as_slice())
+            .is_err());
+
+        let keyring = Keyring::load_from_asc_bytes(&load_asc_keys().1).expect("keyring");
+        assert!(matches!(
+            keyring.verify(&data[..], second_signature.as_slice()),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+    }
+
+    #[test]
+    fn keyring_from_dir() {
+        let tmp = tempfile::tempdir().expect("Should be able to create the key dir");
+        let dir = tmp.path();
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_assets/RPM-GPG-KEY-pmanager"
+            ),
+            dir.join("RPM-GPG-KEY-pmanager"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("RPM-GPG-KEY-second"), load_second_asc_keys().1).unwrap();
+        std::fs::write(dir.join("README"), b"not a key").unwrap();
+
+        let keyring = Keyring::load_from_dir(dir).expect("keyring");
+        assert_eq!(keyring.len(), 2);
+
+        let keyring = Keyring::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/RPM-GPG-KEY-pmanager"
+        ))
+        .expect("keyring");
+        assert_eq!(keyring.len(), 1);
+
+        assert!(Keyring::load_from_asc("not a key").is_err());
+        assert!(Keyring::new().is_empty());
+    }
+
+    /// Load a pair of sample Ed25519 keys.
+    pub(crate) fn load_ed25519_asc_keys() -> (Vec<u8>, Vec<u8>) {
+        let signing_key = include_bytes!("../../../test_assets/ed25519_secret_key.asc");
+        let verification_key = include_bytes!("../../../test_assets/ed25519_public_key.asc");
+        (signing_key.to_vec(), verification_key.to_vec())
+    }
+
+    #[test]
+    fn sign_verify_roundtrip_ed25519() {
+        let (signing_key, verification_key) = load_ed25519_asc_keys();
+        let signer = Signer::load_from_asc_bytes(&signing_key)
+            .expect("PK parsing failed")
+            .with_algorithm::<traits::algorithm::EdDSA>();
+        let verifier = Verifier::load_from_asc_bytes(&verification_key).expect("PK parsing failed");
+
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("signed");
+        let packet = Verifier::parse_signature(&signature).expect("parsable");
+        assert_eq!(
+            packet.config.pub_alg,
+            ::pgp::crypto::public_key::PublicKeyAlgorithm::EdDSA
+        );
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+
+        // an RSA key must not accept it
+        let (_, rsa_verifier) = prep();
+        assert!(rsa_verifier.verify(&data[..], &signature).is_err());
+    }
+
+    #[test]
+    fn sign_with_unsupported_algorithm() {
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+        // the `pgp` crate can not sign with ECDSA keys, whatever the marker
+        let ecdsa_key = include_bytes!("../../../test_assets/ecdsa_secret_key.asc");
+        let signer = Signer::load_from_asc_bytes(&ecdsa_key[..]).expect("PK parsing failed");
+        assert!(matches!(
+            signer.sign(&data[..]),
+            Err(RPMError::UnsupportedPublicKeyAlgorithm { algorithm, .. }) if algorithm == "ECDSA"
+        ));
+        let signer = signer.with_algorithm::<traits::algorithm::EdDSA>();
+        assert!(matches!(
+            signer.sign(&data[..]),
+            Err(RPMError::UnsupportedPublicKeyAlgorithm { algorithm, .. }) if algorithm == "ECDSA"
+        ));
+
+        // nor with a key of another algorithm than the marker
+        let (rsa_signer, _) = prep();
+        let signer = rsa_signer.with_algorithm::<traits::algorithm::EdDSA>();
+        assert!(matches!(
+            signer.sign(&data[..]),
+            Err(RPMError::UnsupportedPublicKeyAlgorithm { algorithm, .. }) if algorithm == "RSA"
+        ));
+    }
+
+    #[test]
+    fn load_binary_keys() {
+        let signing_key = include_bytes!("../../../test_assets/secret_key.gpg");
+        let verification_key = include_bytes!("../../../test_assets/public_key.gpg");
+        let signer = Signer::load_from_bytes(&signing_key[..]).expect("PK parsing failed");
+        let verifier = Verifier::load_from_bytes(&verification_key[..]).expect("PK parsing failed");
+        assert!(Signer::load_from_bytes(&load_asc_keys().0).is_err());
+
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("signed");
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+
+        let mut keyring = Keyring::new();
+        keyring
+            .add_bytes(&verification_key[..])
+            .expect("binary key");
+        keyring
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+    }
+
+    #[test]
+    fn sign_with_protected_key() {
+        let signing_key = include_str!("../../../test_assets/secret_key_protected.asc");
+        let (_, verifier) = prep();
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+        let signer = Signer::load_from_asc(signing_key).expect("PK parsing failed");
+        assert!(matches!(
+            signer.sign(&data[..]),
+            Err(RPMError::SignError(_))
+        ));
+        let signer = signer.with_key_passphrase("wrong");
+        assert!(signer.sign(&data[..]).is_err());
+
+        let signer = signer.with_key_passphrase("rpm-test-passphrase");
+        let signature = signer.sign(&data[..]).expect("signed");
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+        assert!(!format!("{:?}", signer).contains("rpm-test-passphrase"));
+
+        let signer = signer.with_key_passphrase_fn(|| "rpm-test-passphrase".to_owned());
+        let signature = signer.sign(&data[..]).expect("signed");
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+    }
+
+    #[test]
+    fn sign_deterministic() {
+        use ::chrono::offset::TimeZone;
+        use ::pgp::crypto::hash::HashAlgorithm;
+
+        let (signer, verifier) = prep();
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let creation_time = ::chrono::offset::Utc
+            .timestamp_opt(1_600_000_000, 123)
+            .unwrap();
+
+        for hash_algorithm in [
+            HashAlgorithm::SHA2_256,
+            HashAlgorithm::SHA2_384,
+            HashAlgorithm::SHA2_512,
+        ] {
+            let signer = signer
+                .clone()
+                .with_hash_algorithm(hash_algorithm)
+                .expect("SHA2 is accepted")
+                .with_creation_time(creation_time);
+            let signature = signer.sign(&data[..]).expect("signed");
+            assert_eq!(signature, signer.sign(&data[..]).expect("signed"));
+
+            let packet = Verifier::parse_signature(&signature).expect("parsable");
+            assert_eq!(packet.config.hash_alg, hash_algorithm);
+            assert_eq!(
+                packet.created(),
+                Some(
+                    &::chrono::offset::Utc
+                        .timestamp_opt(1_600_000_000, 0)
+                        .unwrap()
+                )
+            );
+            verifier
+                .verify(&data[..], &signature)
+                .expect("failed to verify just signed signature");
+        }
+    }
+
+    #[test]
+    fn reject_weak_hash_algorithms() {
+        use ::pgp::crypto::hash::HashAlgorithm;
+
+        let (signer, _) = prep();
+        for hash_algorithm in [HashAlgorithm::MD5, HashAlgorithm::SHA1] {
+            assert!(matches!(
+                signer.clone().with_hash_algorithm(hash_algorithm),
+                Err(RPMError::UnsupportedHashAlgorithm(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn sign_with_subkey() {
+        let (signer, verifier) = prep();
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let issuer = |signature: &[u8]| {
+            let packet = Verifier::parse_signature(signature).expect("parsable");
+            hex::encode(packet.issuer().expect("issuer"))
+        };
+
+        let subkey_signer = signer
+            .clone()
+            .with_signing_key("77500CC056DB3521")
+            .expect("subkey exists")
+            .with_signers_user_id("Package Manager <pmanager@example.com>");
+        let signature = subkey_signer.sign(&data[..]).expect("signed");
+        assert_eq!(issuer(&signature), "77500cc056db3521");
+        assert_eq!(
+            Verifier::parse_signature(&signature)
+                .expect("parsable")
+                .signers_userid(),
+            Some("Package Manager <pmanager@example.com>")
+        );
+        verifier
+            .verify(&data[..], &signature)
+            .expect("failed to verify just signed signature");
+
+        let primary_signer = subkey_signer
+            .with_signing_key("0xcfd331925ab27f39")
+            .expect("primary key exists");
+        let signature = primary_signer.sign(&data[..]).expect("signed");
+        assert_eq!(issuer(&signature), "cfd331925ab27f39");
+
+        let mut flags = KeyFlags::default();
+        flags.set_sign(true);
+        let flags_signer = signer
+            .clone()
+            .with_signing_subkey_by_flags(flags)
+            .expect("signing subkey exists");
+        let signature = flags_signer.sign(&data[..]).expect("signed");
+        assert_eq!(issuer(&signature), "77500cc056db3521");
+
+        let mut flags = KeyFlags::default();
+        flags.set_certify(true);
+        assert!(matches!(
+            signer.clone().with_signing_subkey_by_flags(flags),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+        assert!(matches!(
+            signer.with_signing_key("0000000000000000"),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+    }
+
+    #[test]
+    fn select_signing_subkey_by_flags_when_rotating() {
+        // signing subkeys of 2021 and 2022, one of 2023 expired in 2024
+        // and one of 2024 that was revoked
+        let signer = Signer::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/rotation_secret_key.asc"
+        ))
+        .expect("PK parsing failed");
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let mut flags = KeyFlags::default();
+        flags.set_sign(true);
+        let issuer_at = |year: i32| {
+            let mut signer = signer.clone();
+            if year > 0 {
+                use ::chrono::TimeZone;
+                signer = signer.with_creation_time(
+                    ::chrono::Utc.with_ymd_and_hms(year, 6, 1, 0, 0, 0).unwrap(),
+                );
+            }
+            let signature = signer
+                .with_signing_subkey_by_flags(flags)?
+                .sign(&data[..])
+                .expect("signed");
+            let packet = Verifier::parse_signature(&signature).expect("parsable");
+            Ok::<_, RPMError>(hex::encode(packet.issuer().expect("issuer")))
+        };
+
+        assert_eq!(issuer_at(0).unwrap(), "fd54719ac4e4f308");
+        assert_eq!(issuer_at(2025).unwrap(), "fd54719ac4e4f308");
+        assert_eq!(issuer_at(2023).unwrap(), "b468942d8cd59e6a");
+        assert_eq!(issuer_at(2021).unwrap(), "95627143610d920b");
+        assert!(matches!(
+            issuer_at(2020),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+    }
+
     #[test]
     fn parse_asc() {
         // assert `prep()` itself is sane
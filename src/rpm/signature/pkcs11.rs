@@ -0,0 +1,528 @@
+//! Signer for RSA keys held by a PKCS#11 token, i.e. a hardware security module.
+//!
+//! The token only creates the raw RSA signature, the OpenPGP signature packet
+//! around it is assembled the same way [`pgp::Signer`](super::pgp::Signer) does.
+
+use super::pgp::{serialize_signature, signature_config};
+use super::traits;
+use super::{now, truncate_to_seconds};
+use crate::errors::RPMError;
+
+use std::io::Read;
+use std::path::Path;
+
+use ::cryptoki::context::{CInitializeArgs, Pkcs11};
+use ::cryptoki::mechanism::Mechanism;
+use ::cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use ::cryptoki::session::{Session, SessionFlags, UserType};
+use ::cryptoki::slot::Slot;
+use ::pgp::composed::Deserializable;
+use ::pgp::crypto::hash::HashAlgorithm;
+use ::pgp::crypto::public_key::PublicKeyAlgorithm;
+use ::pgp::packet::{Signature, SignatureConfig};
+use ::pgp::types::{KeyId, KeyTrait, Mpi, PublicParams};
+
+// DER encoded `DigestInfo` prefixes, see RFC 8017 section 9.2
+const DIGEST_INFO_SHA2_224: &[u8] = &[
+    0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04, 0x05,
+    0x00, 0x04, 0x1c,
+];
+const DIGEST_INFO_SHA2_256: &[u8] = &[
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+const DIGEST_INFO_SHA2_384: &[u8] = &[
+    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05,
+    0x00, 0x04, 0x30,
+];
+const DIGEST_INFO_SHA2_512: &[u8] = &[
+    0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05,
+    0x00, 0x04, 0x40,
+];
+
+/// Signer implementation using a RSA key stored in a PKCS#11 token.
+///
+/// The private key is looked up by its label and never leaves the token.
+/// The OpenPGP public key of the same key pair provides the key ID
+/// the signatures are issued by, the matching (sub)key is picked by
+/// comparing the RSA modulus.
+///
+/// Signatures use SHA2-256 and the current time unless configured otherwise.
+#[derive(Clone)]
+pub struct Signer {
+    context: Pkcs11,
+    slot: Slot,
+    key_label: String,
+    user_pin: String,
+    key_id: KeyId,
+    hash_algorithm: HashAlgorithm,
+    creation_time: Option<::chrono::DateTime<::chrono::Utc>>,
+}
+
+impl std::fmt::Debug for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the pin
+        f.debug_struct("Signer")
+            .field("slot", &self.slot)
+            .field("key_label", &self.key_label)
+            .field("key_id", &self.key_id)
+            .field("hash_algorithm", &self.hash_algorithm)
+            .field("creation_time", &self.creation_time)
+            .finish()
+    }
+}
+
+fn key_load_error(
+    source: impl Into<Box<dyn std::error::Error>>,
+    details: &'static str,
+) -> RPMError {
+    RPMError::KeyLoadError {
+        source: source.into(),
+        details,
+    }
+}
+
+fn sign_error(error: impl Into<Box<dyn std::error::Error>>) -> RPMError {
+    RPMError::SignError(error.into())
+}
+
+/// The `DigestInfo` prefix of `hash_algorithm` for a PKCS#1 v1.5 signature.
+fn digest_info_prefix(hash_algorithm: HashAlgorithm) -> Result<&'static [u8], RPMError> {
+    match hash_algorithm {
+        HashAlgorithm::SHA2_224 => Ok(DIGEST_INFO_SHA2_224),
+        HashAlgorithm::SHA2_256 => Ok(DIGEST_INFO_SHA2_256),
+        HashAlgorithm::SHA2_384 => Ok(DIGEST_INFO_SHA2_384),
+        HashAlgorithm::SHA2_512 => Ok(DIGEST_INFO_SHA2_512),
+        hash_algorithm => Err(RPMError::UnsupportedHashAlgorithm(format!(
+            "{:?}",
+            hash_algorithm
+        ))),
+    }
+}
+
+impl Signer {
+    /// Load the PKCS#11 `module` and use the RSA private key labeled `key_label`
+    /// of the token labeled `token_label`.
+    ///
+    /// `public_key` is the OpenPGP public key of the key pair, ascii armored or binary.
+    pub fn new(
+        module: impl AsRef<Path>,
+        token_label: &str,
+        key_label: &str,
+        user_pin: impl Into<String>,
+        public_key: &[u8],
+    ) -> Result<Self, RPMError> {
+        let context = Pkcs11::new(module)
+            .map_err(|e| key_load_error(e, "Failed to load the PKCS#11 module"))?;
+        context
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(|e| key_load_error(e, "Failed to initialize the PKCS#11 module"))?;
+        Self::with_context(context, token_label, key_label, user_pin, public_key)
+    }
+
+    /// Same as [`new`](Self::new) but with an already initialized context.
+    pub fn with_context(
+        context: Pkcs11,
+        token_label: &str,
+        key_label: &str,
+        user_pin: impl Into<String>,
+        public_key: &[u8],
+    ) -> Result<Self, RPMError> {
+        let slots = context
+            .get_slots_with_token()
+            .map_err(|e| key_load_error(e, "Failed to list the PKCS#11 slots"))?;
+        let slot = slots
+            .into_iter()
+            .find(|slot| {
+                context
+                    .get_token_info(*slot)
+                    .map(|info| info.label().trim_end() == token_label)
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| {
+                key_load_error(
+                    format!("no token labeled {:?}", token_label),
+                    "Failed to find the PKCS#11 token",
+                )
+            })?;
+
+        let public_key = parse_public_key(public_key)?;
+
+        let mut signer = Self {
+            context,
+            slot,
+            key_label: key_label.to_owned(),
+            user_pin: user_pin.into(),
+            key_id: public_key.key_id(),
+            hash_algorithm: HashAlgorithm::SHA2_256,
+            creation_time: None,
+        };
+
+        let session = signer
+            .open_session()
+            .map_err(|e| key_load_error(e, "Failed to open a PKCS#11 session"))?;
+        let key = signer
+            .find_key(&session)
+            .map_err(|e| key_load_error(e, "Failed to find the private key in the token"))?;
+        let modulus = session
+            .get_attributes(key, &[AttributeType::Modulus])
+            .map_err(|e| key_load_error(e, "Failed to read the modulus of the private key"))?
+            .into_iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Modulus(modulus) => Some(Mpi::from_raw(modulus)),
+                _ => None,
+            });
+
+        let is_token_key = |params: &PublicParams| match params {
+            PublicParams::RSA { n, .. } => Some(n) == modulus.as_ref(),
+            _ => false,
+        };
+        signer.key_id = if is_token_key(public_key.primary_key.public_params()) {
+            public_key.key_id()
+        } else {
+            public_key
+                .public_subkeys
+                .iter()
+                .find(|subkey| is_token_key(subkey.key.public_params()))
+                .map(|subkey| subkey.key.key_id())
+                .ok_or_else(|| {
+                    key_load_error(
+                        format!("no key of {:?} matches", hex::encode(public_key.key_id())),
+                        "The public key does not belong to the private key in the token",
+                    )
+                })?
+        };
+        Ok(signer)
+    }
+
+    /// Hash algorithm used for signing, SHA2-256 by default.
+    ///
+    /// Only the SHA2 family is supported, anything else is rejected.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Result<Self, RPMError> {
+        digest_info_prefix(hash_algorithm)?;
+        self.hash_algorithm = hash_algorithm;
+        Ok(self)
+    }
+
+    /// Fixed creation time of the signatures instead of the current time.
+    pub fn with_creation_time(mut self, creation_time: ::chrono::DateTime<::chrono::Utc>) -> Self {
+        self.creation_time = Some(truncate_to_seconds(creation_time));
+        self
+    }
+
+    fn open_session(&self) -> Result<Session, ::cryptoki::error::Error> {
+        use ::cryptoki::error::{Error, RvError};
+
+        let mut flags = SessionFlags::new();
+        flags.set_serial_session(true);
+        let session = self.context.open_session_no_callback(self.slot, flags)?;
+        // the login is shared by all sessions of the application, i.e. of a cloned signer
+        match session.login(UserType::User, Some(&self.user_pin)) {
+            Ok(()) | Err(Error::Pkcs11(RvError::UserAlreadyLoggedIn)) => Ok(session),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn find_key(&self, session: &Session) -> Result<ObjectHandle, RPMError> {
+        let template = [
+            Attribute::Class(ObjectClass::PRIVATE_KEY),
+            Attribute::KeyType(KeyType::RSA),
+            Attribute::Label(self.key_label.as_bytes().to_vec()),
+        ];
+        session
+            .find_objects(&template)
+            .map_err(sign_error)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| sign_error(format!("no RSA private key labeled {:?}", self.key_label)))
+    }
+}
+
+/// Parse an ascii armored or binary OpenPGP public key.
+fn parse_public_key(
+    input: &[u8],
+) -> Result<::pgp::composed::signed_key::SignedPublicKey, RPMError> {
+    use ::pgp::composed::signed_key::SignedPublicKey;
+
+    match std::str::from_utf8(input) {
+        Ok(armored) if armored.trim_start().starts_with("-----BEGIN PGP") => {
+            SignedPublicKey::from_string(armored)
+                .map(|(public_key, _)| public_key)
+                .map_err(|e| key_load_error(e, "Failed to parse bytes as ascii armored key"))
+        }
+        _ => SignedPublicKey::from_bytes(input)
+            .map_err(|e| key_load_error(e, "Failed to parse bytes as binary key")),
+    }
+}
+
+/// Hash `data` as described by `config` and wrap the signature of the
+/// resulting `DigestInfo` created by `sign_digest_info` into a signature packet.
+pub(crate) fn create_signature<R, F>(
+    config: SignatureConfig,
+    data: R,
+    sign_digest_info: F,
+) -> Result<Signature, RPMError>
+where
+    R: Read,
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, RPMError>,
+{
+    let prefix = digest_info_prefix(config.hash_alg)?;
+
+    let mut hasher = config.hash_alg.new_hasher().map_err(sign_error)?;
+    config
+        .hash_data_to_sign(&mut *hasher, data)
+        .map_err(sign_error)?;
+    let len = config
+        .hash_signature_data(&mut *hasher)
+        .map_err(sign_error)?;
+    hasher.update(&config.trailer(len));
+    let hash = hasher.finish();
+
+    let mut digest_info = Vec::with_capacity(prefix.len() + hash.len());
+    digest_info.extend_from_slice(prefix);
+    digest_info.extend_from_slice(&hash);
+    let signature = sign_digest_info(&digest_info)?;
+
+    Ok(Signature::from_config(
+        config,
+        [hash[0], hash[1]],
+        vec![Mpi::from_raw(signature)],
+    ))
+}
+
+impl traits::Signing<traits::algorithm::RSA> for Signer {
+    type Signature = Vec<u8>;
+
+    fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+        let config = signature_config(
+            self.key_id.clone(),
+            PublicKeyAlgorithm::RSA,
+            self.hash_algorithm,
+            self.creation_time.unwrap_or_else(now),
+            None,
+        );
+
+        let session = self.open_session().map_err(sign_error)?;
+        let key = self.find_key(&session)?;
+        let signature_packet = create_signature(config, data, |digest_info| {
+            session
+                .sign(&Mechanism::RsaPkcs, key, digest_info)
+                .map_err(sign_error)
+        })?;
+
+        serialize_signature(&signature_packet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signature::{pgp, Signing, Verifying};
+
+    use ::pgp::composed::signed_key::SignedSecretKey;
+    use ::pgp::types::{PlainSecretParams, SecretParams};
+
+    /// The RSA components `n`, `e`, `d`, `p` and `q` of the test signing key.
+    fn rsa_components() -> [Vec<u8>; 5] {
+        let (signing_key, _) = pgp::test::load_asc_keys();
+        let (secret_key, _) =
+            SignedSecretKey::from_string(std::str::from_utf8(&signing_key).unwrap()).unwrap();
+        let (n, e) = match secret_key.primary_key.public_params() {
+            PublicParams::RSA { n, e } => (n, e),
+            _ => unreachable!("the test key is a RSA key"),
+        };
+        match secret_key.primary_key.secret_params() {
+            SecretParams::Plain(PlainSecretParams::RSA { d, p, q, .. }) => [
+                n.as_bytes().to_vec(),
+                e.as_bytes().to_vec(),
+                d.as_bytes().to_vec(),
+                p.as_bytes().to_vec(),
+                q.as_bytes().to_vec(),
+            ],
+            _ => unreachable!("the test key is not protected"),
+        }
+    }
+
+    #[test]
+    fn packet_matches_pgp_signer() {
+        use ::rsa::{BigUint, PaddingScheme, RsaPrivateKey};
+
+        let [n, e, d, p, q] = rsa_components();
+        let private_key = RsaPrivateKey::from_components(
+            BigUint::from_bytes_be(&n),
+            BigUint::from_bytes_be(&e),
+            BigUint::from_bytes_be(&d),
+            vec![BigUint::from_bytes_be(&p), BigUint::from_bytes_be(&q)],
+        );
+
+        let (signing_key, verification_key) = pgp::test::load_asc_keys();
+        let created = ::chrono::DateTime::parse_from_rfc3339("2021-08-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&::chrono::Utc);
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+        for hash_algorithm in [HashAlgorithm::SHA2_256, HashAlgorithm::SHA2_512] {
+            let signer = pgp::Signer::load_from_asc_bytes(&signing_key)
+                .unwrap()
+                .with_hash_algorithm(hash_algorithm)
+                .unwrap()
+                .with_creation_time(created);
+            let expected = signer.sign(&data[..]).unwrap();
+
+            let (public_key, _) = ::pgp::composed::signed_key::SignedPublicKey::from_string(
+                std::str::from_utf8(&verification_key).unwrap(),
+            )
+            .unwrap();
+            let config = signature_config(
+                public_key.key_id(),
+                PublicKeyAlgorithm::RSA,
+                hash_algorithm,
+                created,
+                None,
+            );
+            // mimics the `CKM_RSA_PKCS` mechanism of a token
+            let packet = create_signature(config, &data[..], |digest_info| {
+                private_key
+                    .sign(PaddingScheme::new_pkcs1v15_sign(None), digest_info)
+                    .map_err(sign_error)
+            })
+            .unwrap();
+            let signature = serialize_signature(&packet).unwrap();
+
+            assert_eq!(expected, signature);
+            let verifier = pgp::Verifier::load_from_asc_bytes(&verification_key).unwrap();
+            verifier.verify(&data[..], &signature).unwrap();
+        }
+    }
+
+    #[test]
+    fn load_armored_and_binary_public_key() {
+        let (_, armored) = pgp::test::load_asc_keys();
+        let binary = include_bytes!("../../../test_assets/public_key.gpg");
+        assert_eq!(
+            parse_public_key(&armored).unwrap().key_id(),
+            parse_public_key(&binary[..]).unwrap().key_id()
+        );
+        assert!(parse_public_key(b"not a key").is_err());
+    }
+
+    /// Set to the SoftHSM config of the child process running [`softhsm_sign`].
+    const SOFTHSM_TEST_CONF: &str = "RPM_RS_SOFTHSM_TEST_CONF";
+
+    /// Run with `SOFTHSM2_MODULE=/usr/lib/softhsm/libsofthsm2.so cargo test --features signature-pkcs11 -- --ignored`.
+    ///
+    /// The token is created in a throwaway SoftHSM token directory, existing tokens are
+    /// left untouched. SoftHSM reads its config from `SOFTHSM2_CONF` only, so the test
+    /// runs again in a child process with the config passed in its environment.
+    #[test]
+    #[ignore = "requires SoftHSM, set SOFTHSM2_MODULE"]
+    fn softhsm_sign() {
+        let module = std::env::var_os("SOFTHSM2_MODULE").expect("SOFTHSM2_MODULE is set");
+
+        if std::env::var_os(SOFTHSM_TEST_CONF).is_none() {
+            let softhsm_dir = tempfile::tempdir().expect("Should be able to create the token dir");
+            let token_dir = softhsm_dir.path().join("tokens");
+            std::fs::create_dir(&token_dir).expect("Should be able to create the token dir");
+            let conf = softhsm_dir.path().join("softhsm2.conf");
+            std::fs::write(
+                &conf,
+                format!(
+                    "directories.tokendir = {}\nobjectstore.backend = file\n",
+                    token_dir.display()
+                ),
+            )
+            .expect("Should be able to write the SoftHSM config");
+
+            let status = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "rpm::signature::pkcs11::test::softhsm_sign",
+                    "--ignored",
+                ])
+                .env("SOFTHSM2_CONF", &conf)
+                .env(SOFTHSM_TEST_CONF, &conf)
+                .status()
+                .expect("test binary runs");
+            assert!(status.success());
+            return;
+        }
+        assert_eq!(
+            std::env::var_os("SOFTHSM2_CONF"),
+            std::env::var_os(SOFTHSM_TEST_CONF)
+        );
+
+        let context = Pkcs11::new(module).expect("module loads");
+        context
+            .initialize(CInitializeArgs::OsThreads)
+            .expect("module initializes");
+
+        // a fresh token holding the test key, in a slot without an initialized token
+        let slot = context
+            .get_slots_with_token()
+            .expect("slots")
+            .into_iter()
+            .find(|slot| {
+                !context
+                    .get_token_info(*slot)
+                    .expect("token info")
+                    .flags()
+                    .token_initialized()
+            })
+            .expect("a free slot");
+        context
+            .init_token(slot, "1234", "rpm-rs-test")
+            .expect("token initializes");
+        let mut flags = SessionFlags::new();
+        flags.set_serial_session(true).set_rw_session(true);
+        {
+            let session = context
+                .open_session_no_callback(slot, flags)
+                .expect("session opens");
+            session.login(UserType::So, Some("1234")).unwrap();
+            session.init_pin("5678").unwrap();
+        }
+        let session = context
+            .open_session_no_callback(slot, flags)
+            .expect("session opens");
+        session.login(UserType::User, Some("5678")).unwrap();
+        let [n, e, d, p, q] = rsa_components();
+        session
+            .create_object(&[
+                Attribute::Class(ObjectClass::PRIVATE_KEY),
+                Attribute::KeyType(KeyType::RSA),
+                Attribute::Token(true),
+                Attribute::Private(true),
+                Attribute::Sign(true),
+                Attribute::Label(b"rpm-signing".to_vec()),
+                Attribute::Modulus(n),
+                Attribute::PublicExponent(e),
+                Attribute::PrivateExponent(d),
+                Attribute::Prime1(p),
+                Attribute::Prime2(q),
+            ])
+            .expect("key imports");
+        drop(session);
+
+        let (_, verification_key) = pgp::test::load_asc_keys();
+        let signer = Signer::with_context(
+            context,
+            "rpm-rs-test",
+            "rpm-signing",
+            "5678",
+            &verification_key,
+        )
+        .expect("signer finds the key");
+        let data = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+        let signature = signer.sign(&data[..]).expect("token signs");
+
+        let verifier = pgp::Verifier::load_from_asc_bytes(&verification_key).unwrap();
+        verifier.verify(&data[..], &signature).unwrap();
+
+        // a clone logs in while the session of the other one is still open
+        let clone = signer.clone();
+        let session = signer.open_session().expect("session opens");
+        let signature = clone.sign(&data[..]).expect("clone signs");
+        drop(session);
+        verifier.verify(&data[..], &signature).unwrap();
+    }
+}
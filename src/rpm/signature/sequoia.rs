@@ -0,0 +1,488 @@
+//! Signer and verifier implementations using the `sequoia-openpgp` crate.
+//!
+//! An alternative to the [`pgp`](super::pgp) backend, keys and certificates
+//! are validated according to sequoia's standard policy.
+
+use super::traits;
+use crate::errors::RPMError;
+
+use std::io::{self, Read, Write};
+
+use ::sequoia_openpgp as openpgp;
+use openpgp::cert::{Cert, CertParser};
+use openpgp::crypto::Password;
+use openpgp::packet::Signature;
+use openpgp::parse::Parse;
+use openpgp::policy::{HashAlgoSecurity, Policy, StandardPolicy};
+use openpgp::serialize::stream::{Message, Signer as StreamSigner};
+use openpgp::types::{HashAlgorithm, PublicKeyAlgorithm};
+use openpgp::{KeyHandle, Packet};
+
+fn sign_error(error: impl Into<Box<dyn std::error::Error>>) -> RPMError {
+    RPMError::SignError(error.into())
+}
+
+fn key_load_error(
+    source: impl Into<Box<dyn std::error::Error>>,
+    details: &'static str,
+) -> RPMError {
+    RPMError::KeyLoadError {
+        source: source.into(),
+        details,
+    }
+}
+
+/// Parse a key ID or fingerprint as hex, optionally prefixed with `0x`.
+fn parse_key_handle(key_ref: &str) -> Result<KeyHandle, RPMError> {
+    let hex = key_ref.trim_start_matches("0x");
+    hex.parse::<KeyHandle>()
+        .map_err(|e| key_load_error(e, "Failed to parse the key reference"))
+}
+
+/// Signer implementation using the `sequoia-openpgp` crate.
+///
+/// Keys can be loaded ascii armored or binary. The first valid signing capable
+/// key of the certificate is used, unless selected via
+/// [`with_signing_key`](Self::with_signing_key).
+///
+/// Signatures use SHA2-256 and the current time unless configured otherwise.
+/// The algorithm marker `A` has to match the signing key, [`RSA`](traits::algorithm::RSA)
+/// as loaded, see [`with_algorithm`](Self::with_algorithm).
+#[derive(Clone)]
+pub struct Signer<A = traits::algorithm::RSA> {
+    cert: Cert,
+    key_passphrase: Option<Password>,
+    hash_algorithm: HashAlgorithm,
+    creation_time: Option<::chrono::DateTime<::chrono::Utc>>,
+    signing_key: Option<KeyHandle>,
+    algorithm: std::marker::PhantomData<A>,
+}
+
+impl<A> std::fmt::Debug for Signer<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the passphrase
+        f.debug_struct("Signer")
+            .field("cert", &self.cert.fingerprint())
+            .field("hash_algorithm", &self.hash_algorithm)
+            .field("creation_time", &self.creation_time)
+            .field("signing_key", &self.signing_key)
+            .finish()
+    }
+}
+
+impl traits::Signing<traits::algorithm::RSA> for Signer<traits::algorithm::RSA> {
+    type Signature = Vec<u8>;
+
+    // sign only keys are deprecated, but still valid
+    #[allow(deprecated)]
+    fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+        self.sign_checked(
+            &[
+                PublicKeyAlgorithm::RSAEncryptSign,
+                PublicKeyAlgorithm::RSASign,
+            ],
+            data,
+        )
+    }
+}
+
+impl traits::Signing<traits::algorithm::EdDSA> for Signer<traits::algorithm::EdDSA> {
+    type Signature = Vec<u8>;
+
+    fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+        self.sign_checked(&[PublicKeyAlgorithm::EdDSA], data)
+    }
+}
+
+impl traits::Signing<traits::algorithm::ECDSA> for Signer<traits::algorithm::ECDSA> {
+    type Signature = Vec<u8>;
+
+    fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+        self.sign_checked(&[PublicKeyAlgorithm::ECDSA], data)
+    }
+}
+
+impl<A> Signer<A> {
+    /// Sign with the selected key, if its algorithm is one of `expected`.
+    fn sign_checked<R: Read>(
+        &self,
+        expected: &[PublicKeyAlgorithm],
+        mut data: R,
+    ) -> Result<Vec<u8>, RPMError> {
+        let created = self.creation_time.unwrap_or_else(super::now).into();
+        let policy = StandardPolicy::new();
+
+        let key = self
+            .cert
+            .keys()
+            .with_policy(&policy, Some(created))
+            .supported()
+            .alive()
+            .revoked(false)
+            .for_signing()
+            .secret()
+            .map(|amalgamation| amalgamation.key().clone())
+            .find(|key| match self.signing_key {
+                Some(ref handle) => key.key_handle().aliases(handle),
+                None => true,
+            })
+            .ok_or_else(|| RPMError::KeyNotFoundError {
+                key_ref: match self.signing_key {
+                    Some(ref handle) => handle.to_hex(),
+                    None => self.cert.fingerprint().to_hex(),
+                },
+            })?;
+        if !expected.contains(&key.pk_algo()) {
+            return Err(RPMError::UnsupportedPublicKeyAlgorithm {
+                algorithm: key.pk_algo().to_string(),
+                reason:
+                    "it does not match the algorithm of the signer, see `Signer::with_algorithm`",
+            });
+        }
+        let key = if key.secret().is_encrypted() {
+            let passphrase = self
+                .key_passphrase
+                .clone()
+                .unwrap_or_else(|| Password::from(""));
+            key.decrypt_secret(&passphrase).map_err(sign_error)?
+        } else {
+            key
+        };
+        let keypair = key.into_keypair().map_err(sign_error)?;
+
+        let mut signature = Vec::with_capacity(1024);
+        {
+            let message = Message::new(&mut signature);
+            let mut signer = StreamSigner::new(message, keypair)
+                .detached()
+                .hash_algo(self.hash_algorithm)
+                .map_err(sign_error)?
+                .creation_time(created)
+                .build()
+                .map_err(sign_error)?;
+            io::copy(&mut data, &mut signer)?;
+            signer.flush()?;
+            signer.finalize().map_err(sign_error)?;
+        }
+        Ok(signature)
+    }
+
+    /// Use the signer for a key of algorithm `B`, i.e. [`EdDSA`](traits::algorithm::EdDSA)
+    /// for an Ed25519 key.
+    pub fn with_algorithm<B>(self) -> Signer<B> {
+        Signer {
+            cert: self.cert,
+            key_passphrase: self.key_passphrase,
+            hash_algorithm: self.hash_algorithm,
+            creation_time: self.creation_time,
+            signing_key: self.signing_key,
+            algorithm: std::marker::PhantomData,
+        }
+    }
+}
+
+impl Signer {
+    fn new(cert: Cert) -> Result<Self, RPMError> {
+        if !cert.is_tsk() {
+            return Err(key_load_error(
+                "no secret key material",
+                "The certificate does not contain a secret key",
+            ));
+        }
+        Ok(Self {
+            cert,
+            key_passphrase: None,
+            hash_algorithm: HashAlgorithm::SHA256,
+            creation_time: None,
+            signing_key: None,
+            algorithm: std::marker::PhantomData,
+        })
+    }
+
+    /// load the private key for signing
+    pub fn load_from_asc_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        Self::load_from_bytes(input)
+    }
+
+    pub fn load_from_asc(input: &str) -> Result<Self, RPMError> {
+        Self::load_from_bytes(input.as_bytes())
+    }
+
+    /// load the private key for signing, either ascii armored or binary
+    pub fn load_from_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let cert = Cert::from_bytes(input)
+            .map_err(|e| key_load_error(e, "Failed to parse bytes as key"))?;
+        Self::new(cert)
+    }
+}
+
+impl<A> Signer<A> {
+    /// Passphrase to decrypt a protected secret key.
+    pub fn with_key_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.key_passphrase = Some(Password::from(passphrase.into()));
+        self
+    }
+
+    /// Hash algorithm used for signing, SHA2-256 by default.
+    ///
+    /// Only the SHA2 family is accepted, anything else is rejected.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Result<Self, RPMError> {
+        match hash_algorithm {
+            HashAlgorithm::SHA224
+            | HashAlgorithm::SHA256
+            | HashAlgorithm::SHA384
+            | HashAlgorithm::SHA512 => {
+                self.hash_algorithm = hash_algorithm;
+                Ok(self)
+            }
+            hash_algorithm => Err(RPMError::UnsupportedHashAlgorithm(
+                hash_algorithm.to_string(),
+            )),
+        }
+    }
+
+    /// Fixed creation time of the signatures instead of the current time.
+    pub fn with_creation_time(mut self, creation_time: ::chrono::DateTime<::chrono::Utc>) -> Self {
+        self.creation_time = Some(super::truncate_to_seconds(creation_time));
+        self
+    }
+
+    /// Sign with the primary key or subkey with the given key ID or fingerprint.
+    pub fn with_signing_key(mut self, key_ref: &str) -> Result<Self, RPMError> {
+        let handle = parse_key_handle(key_ref)?;
+        if !self
+            .cert
+            .keys()
+            .any(|amalgamation| amalgamation.key().key_handle().aliases(&handle))
+        {
+            return Err(RPMError::KeyNotFoundError {
+                key_ref: key_ref.to_owned(),
+            });
+        }
+        self.signing_key = Some(handle);
+        Ok(self)
+    }
+}
+
+/// Verifier implementation using the `sequoia-openpgp` crate.
+///
+/// Holds any number of certificates, the key matching the issuer
+/// of a signature is looked up among all their valid signing keys.
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    certs: Vec<Cert>,
+}
+
+impl Verifier {
+    /// load the public key(s) for verification
+    pub fn load_from_asc_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        Self::load_from_bytes(input)
+    }
+
+    pub fn load_from_asc(input: &str) -> Result<Self, RPMError> {
+        Self::load_from_bytes(input.as_bytes())
+    }
+
+    /// load the public key(s) for verification, either ascii armored or binary
+    pub fn load_from_bytes(input: &[u8]) -> Result<Self, RPMError> {
+        let certs = CertParser::from_bytes(input)
+            .and_then(|parser| parser.collect::<openpgp::Result<Vec<Cert>>>())
+            .map_err(|e| key_load_error(e, "Failed to parse bytes as key"))?;
+        if certs.is_empty() {
+            return Err(key_load_error(
+                "no certificate found",
+                "Failed to parse bytes as key",
+            ));
+        }
+        Ok(Self { certs })
+    }
+}
+
+impl traits::Verifying<traits::algorithm::RSA> for Verifier {
+    type Signature = Vec<u8>;
+
+    fn verify<R: Read>(&self, mut data: R, signature: &[u8]) -> Result<(), RPMError> {
+        let mut signature = match Packet::from_bytes(signature) {
+            Ok(Packet::Signature(signature)) => signature,
+            _ => return Err(RPMError::NoSignatureFound),
+        };
+        let verification_error = |source: Box<dyn std::error::Error>, key_ref: String| {
+            RPMError::VerificationError { source, key_ref }
+        };
+
+        let policy = StandardPolicy::new();
+        policy
+            .signature(&signature, HashAlgoSecurity::CollisionResistance)
+            .map_err(|e| verification_error(e.into(), issuers_ref(&signature)))?;
+
+        let issuers = signature.get_issuers();
+        let created = signature.signature_creation_time();
+        let keys = self
+            .certs
+            .iter()
+            .flat_map(|cert| {
+                cert.keys()
+                    .with_policy(&policy, created)
+                    .for_signing()
+                    .map(|amalgamation| amalgamation.key().clone())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|key| {
+                issuers.is_empty()
+                    || issuers
+                        .iter()
+                        .any(|issuer| key.key_handle().aliases(issuer))
+            })
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Err(RPMError::KeyNotFoundError {
+                key_ref: issuers_ref(&signature),
+            });
+        }
+
+        let mut hash = signature
+            .hash_algo()
+            .context()
+            .map_err(|e| verification_error(e.into(), issuers_ref(&signature)))?;
+        io::copy(&mut data, &mut hash)?;
+
+        let mut result = Ok(());
+        for key in keys {
+            match signature.verify_hash(&key, hash.clone()) {
+                Ok(()) => return Ok(()),
+                Err(e) => result = Err(verification_error(e.into(), key.fingerprint().to_hex())),
+            }
+        }
+        result
+    }
+}
+
+fn issuers_ref(signature: &Signature) -> String {
+    signature
+        .get_issuers()
+        .iter()
+        .map(|issuer| issuer.to_hex())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Signing, Verifying};
+    use super::*;
+
+    fn load_asc_keys() -> (Vec<u8>, Vec<u8>) {
+        let signing_key = include_bytes!("../../../test_assets/secret_key.asc");
+        let verification_key = include_bytes!("../../../test_assets/public_key.asc");
+        (signing_key.to_vec(), verification_key.to_vec())
+    }
+
+    const DATA: &[u8] = b"dfsdfjsd9ivnq320348934752312308205723900000580134850sdf";
+
+    #[test]
+    fn sign_verify_roundtrip() {
+        let (signing_key, verification_key) = load_asc_keys();
+        let signer = Signer::load_from_asc_bytes(&signing_key).expect("key loads");
+        let verifier = Verifier::load_from_asc_bytes(&verification_key).expect("key loads");
+
+        let signature = signer.sign(DATA).expect("signs");
+        verifier.verify(DATA, &signature).expect("verifies");
+        assert!(verifier.verify(&DATA[1..], &signature).is_err());
+
+        // binary keys, a passphrase protected key and ed25519
+        let signer = Signer::load_from_bytes(include_bytes!("../../../test_assets/secret_key.gpg"))
+            .expect("binary key loads");
+        let verifier =
+            Verifier::load_from_bytes(include_bytes!("../../../test_assets/public_key.gpg"))
+                .expect("binary key loads");
+        verifier
+            .verify(DATA, &signer.sign(DATA).expect("signs"))
+            .expect("verifies");
+
+        let signer = Signer::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/secret_key_protected.asc"
+        ))
+        .expect("protected key loads");
+        assert!(signer.sign(DATA).is_err());
+        let signer = signer.with_key_passphrase("rpm-test-passphrase");
+        verifier
+            .verify(DATA, &signer.sign(DATA).expect("signs"))
+            .expect("verifies");
+
+        let signer = Signer::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/ed25519_secret_key.asc"
+        ))
+        .expect("ed25519 key loads");
+        let ed25519_verifier = Verifier::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/ed25519_public_key.asc"
+        ))
+        .expect("ed25519 key loads");
+        assert!(matches!(
+            signer.sign(DATA),
+            Err(RPMError::UnsupportedPublicKeyAlgorithm { .. })
+        ));
+        let signer = signer.with_algorithm::<traits::algorithm::EdDSA>();
+        let signature = signer.sign(DATA).expect("signs");
+        ed25519_verifier.verify(DATA, &signature).expect("verifies");
+        assert!(matches!(
+            verifier.verify(DATA, &signature),
+            Err(RPMError::KeyNotFoundError { .. })
+        ));
+
+        let ecdsa_key = include_bytes!("../../../test_assets/ecdsa_secret_key.asc");
+        let signer = Signer::load_from_asc_bytes(ecdsa_key)
+            .expect("ecdsa key loads")
+            .with_algorithm::<traits::algorithm::ECDSA>();
+        let ecdsa_verifier = Verifier::load_from_asc_bytes(ecdsa_key).expect("ecdsa key loads");
+        let signature = signer.sign(DATA).expect("signs");
+        ecdsa_verifier.verify(DATA, &signature).expect("verifies");
+    }
+
+    #[test]
+    fn select_signing_subkey() {
+        let signer = Signer::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/second_secret_key.asc"
+        ))
+        .expect("key loads")
+        .with_signing_key("0x1016EDA81477963B")
+        .expect("subkey exists");
+        let signature = signer.sign(DATA).expect("signs");
+        assert_eq!(
+            Some("1016eda81477963b".to_owned()),
+            super::super::packet::issuer_key_id(&signature)
+        );
+        let verifier = Verifier::load_from_asc_bytes(include_bytes!(
+            "../../../test_assets/second_public_key.asc"
+        ))
+        .expect("key loads");
+        verifier.verify(DATA, &signature).expect("verifies");
+
+        assert!(signer.with_signing_key("0123456789abcdef").is_err());
+    }
+
+    #[cfg(feature = "signature-pgp")]
+    #[test]
+    fn interoperates_with_pgp_backend() {
+        use ::chrono::TimeZone;
+
+        let (signing_key, verification_key) = load_asc_keys();
+        let created = ::chrono::Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+
+        let signer = Signer::load_from_asc_bytes(&signing_key)
+            .unwrap()
+            .with_creation_time(created);
+        let pgp_verifier =
+            super::super::pgp::Verifier::load_from_asc_bytes(&verification_key).expect("key loads");
+        pgp_verifier
+            .verify(DATA, &signer.sign(DATA).unwrap())
+            .expect("pgp verifies a sequoia signature");
+
+        let pgp_signer = super::super::pgp::Signer::load_from_asc_bytes(&signing_key)
+            .unwrap()
+            .with_creation_time(created);
+        let verifier = Verifier::load_from_asc_bytes(&verification_key).unwrap();
+        verifier
+            .verify(DATA, &pgp_signer.sign(DATA).unwrap())
+            .expect("sequoia verifies a pgp signature");
+    }
+}
@@ -1,4 +1,4 @@
-//! Trait abstractions of signinggraphic operations.
+//! Trait abstractions of cryptographic operations.
 //!
 //! Does not contain hashing! Hashes are fixed by the rpm
 //! "spec" to sha1, md5 (yes, that is correct), sha2_256.
@@ -7,6 +7,8 @@
 use crate::errors::*;
 use std::fmt::Debug;
 use std::io::Read;
+#[cfg(feature = "async-tokio")]
+use tokio::io::AsyncRead;
 
 pub mod algorithm {
 
@@ -21,6 +23,20 @@ pub mod algorithm {
     pub struct RSA;
 
     impl Algorithm for RSA {}
+
+    /// Edwards-curve signatures, i.e. Ed25519, accepted by rpm 4.16 and later
+    #[derive(Debug, Clone, Copy)]
+    pub struct EdDSA;
+
+    impl Algorithm for EdDSA {}
+
+    /// Elliptic curve signatures using the NIST curves
+    ///
+    /// Only the `sequoia` backend signs with these, the `pgp` crate does not implement them.
+    #[derive(Debug, Clone, Copy)]
+    pub struct ECDSA;
+
+    impl Algorithm for ECDSA {}
 }
 
 /// Signing trait to be implement for RPM signing.
@@ -67,6 +83,70 @@ where
     }
 }
 
+/// Asynchronous counterpart of [`Signing`], for signers waiting on i.e. a remote service.
+#[cfg(feature = "async-tokio")]
+#[async_trait::async_trait]
+pub trait AsyncSigning<A>: Debug + Send + Sync
+where
+    A: algorithm::Algorithm,
+    Self::Signature: AsRef<[u8]>,
+{
+    type Signature;
+    async fn sign<R: AsyncRead + Unpin + Send>(&self, data: R)
+        -> Result<Self::Signature, RPMError>;
+}
+
+#[cfg(feature = "async-tokio")]
+#[async_trait::async_trait]
+impl<A, T, S> AsyncSigning<A> for &T
+where
+    T: AsyncSigning<A, Signature = S>,
+    A: algorithm::Algorithm,
+    S: AsRef<[u8]>,
+{
+    type Signature = S;
+    async fn sign<R: AsyncRead + Unpin + Send>(
+        &self,
+        data: R,
+    ) -> Result<Self::Signature, RPMError> {
+        T::sign::<R>(self, data).await
+    }
+}
+
+/// Asynchronous counterpart of [`Verifying`].
+#[cfg(feature = "async-tokio")]
+#[async_trait::async_trait]
+pub trait AsyncVerifying<A>: Debug + Send + Sync
+where
+    A: algorithm::Algorithm,
+    Self::Signature: AsRef<[u8]>,
+{
+    type Signature;
+    async fn verify<R: AsyncRead + Unpin + Send>(
+        &self,
+        data: R,
+        signature: &[u8],
+    ) -> Result<(), RPMError>;
+}
+
+#[cfg(feature = "async-tokio")]
+#[async_trait::async_trait]
+impl<A, T, S> AsyncVerifying<A> for &T
+where
+    T: AsyncVerifying<A, Signature = S>,
+    A: algorithm::Algorithm,
+    S: AsRef<[u8]>,
+{
+    type Signature = S;
+    async fn verify<R: AsyncRead + Unpin + Send>(
+        &self,
+        data: R,
+        signature: &[u8],
+    ) -> Result<(), RPMError> {
+        T::verify::<R>(self, data, signature).await
+    }
+}
+
 pub mod key {
 
     /// Marker trait for key types.
@@ -0,0 +1,499 @@
+//! Integrity checks of parsed packages.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::constants::*;
+use crate::errors::*;
+
+use super::compressor;
+use super::headers::{FileDigestAlgorithm, Header, PublicKeyAlgorithm};
+use super::payload::{self, ArchiveEntryName};
+
+/// Outcome of recomputing a single digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestStatus {
+    /// The digest is not part of the package.
+    NotPresent,
+    /// The recomputed digest matches the stored one.
+    Verified,
+    /// The recomputed digest differs from the stored one.
+    Mismatch {
+        /// hex encoded digest as stored in the package
+        expected: String,
+        /// hex encoded digest as computed from the package content
+        actual: String,
+    },
+}
+
+impl DigestStatus {
+    pub(crate) fn compare(expected: &str, actual: String) -> Self {
+        if expected.eq_ignore_ascii_case(&actual) {
+            DigestStatus::Verified
+        } else {
+            DigestStatus::Mismatch {
+                expected: expected.to_lowercase(),
+                actual,
+            }
+        }
+    }
+
+    pub fn is_verified(&self) -> bool {
+        matches!(self, DigestStatus::Verified)
+    }
+
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, DigestStatus::Mismatch { .. })
+    }
+}
+
+/// Result of [`RPMPackage::verify_digests`](super::RPMPackage::verify_digests).
+///
+/// Every digest rpm knows about is listed, the ones missing
+/// in the package are marked as [`DigestStatus::NotPresent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestReport {
+    /// `RPMSIGTAG_SHA1` spanning the header
+    pub header_sha1: DigestStatus,
+    /// `RPMSIGTAG_SHA256` spanning the header
+    pub header_sha256: DigestStatus,
+    /// `RPMSIGTAG_MD5` spanning the header and the payload
+    pub header_and_payload_md5: DigestStatus,
+    /// `RPMTAG_PAYLOADDIGEST` spanning the compressed payload
+    pub payload: DigestStatus,
+    /// `RPMTAG_PAYLOADDIGESTALT` spanning the uncompressed payload
+    pub payload_uncompressed: DigestStatus,
+}
+
+impl DigestReport {
+    pub(crate) fn all(&self) -> [&DigestStatus; 5] {
+        [
+            &self.header_sha1,
+            &self.header_sha256,
+            &self.header_and_payload_md5,
+            &self.payload,
+            &self.payload_uncompressed,
+        ]
+    }
+
+    /// At least one digest is present and none of the present ones mismatch.
+    pub fn is_ok(&self) -> bool {
+        let all = self.all();
+        all.iter().any(|status| status.is_verified())
+            && !all.iter().any(|status| status.is_mismatch())
+    }
+}
+
+/// Outcome of verifying a single signature.
+#[derive(Debug)]
+pub enum SignatureStatus {
+    /// The signature was made by a key known to the verifier and matches the signed data.
+    Verified,
+    /// Verification failed, i.e. the key is unknown or the signed data was altered.
+    Failed(RPMError),
+}
+
+impl SignatureStatus {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, SignatureStatus::Verified)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, SignatureStatus::Failed(_))
+    }
+}
+
+/// A signature contained in the signature header.
+#[derive(Debug)]
+pub struct SignatureCheck {
+    /// the tag the signature is stored in, which determines the signed data
+    ///
+    /// `RPMSIGTAG_RSA`, `RPMSIGTAG_DSA` and `RPMSIGTAG_OPENPGP` span the header only,
+    /// `RPMSIGTAG_PGP` and `RPMSIGTAG_GPG` the header and the payload.
+    pub tag: IndexSignatureTag,
+    /// key ID of the issuer as 16 lower case hex digits, if the signature names it
+    pub key_id: Option<String>,
+    pub status: SignatureStatus,
+}
+
+impl SignatureCheck {
+    pub(crate) fn new(tag: IndexSignatureTag, signature: &[u8], res: Result<(), RPMError>) -> Self {
+        let status = match res {
+            Ok(()) => SignatureStatus::Verified,
+            Err(e) => SignatureStatus::Failed(e),
+        };
+        Self {
+            tag,
+            key_id: crate::signature::packet::issuer_key_id(signature),
+            status,
+        }
+    }
+}
+
+/// Issuer and algorithms of a signature contained in the signature header.
+///
+/// Obtained by parsing the signature packet without verifying it,
+/// so none of the fields are authenticated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureInfo {
+    /// the tag the signature is stored in
+    ///
+    /// `RPMSIGTAG_OPENPGP` may hold several signatures, which share the tag.
+    pub tag: IndexSignatureTag,
+    /// key ID of the issuer as 16 lower case hex digits, if the signature names it
+    pub key_id: Option<String>,
+    /// fingerprint of the issuer as lower case hex digits, only v4 signatures may name it
+    pub fingerprint: Option<String>,
+    /// `None` if the algorithm is not known to rpm
+    pub public_key_algorithm: Option<PublicKeyAlgorithm>,
+    /// `None` if the algorithm is not known to rpm
+    pub hash_algorithm: Option<FileDigestAlgorithm>,
+    pub creation_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl SignatureInfo {
+    pub(crate) fn parse(tag: IndexSignatureTag, signature: &[u8]) -> Result<Self, RPMError> {
+        use chrono::TimeZone;
+        use num_traits::FromPrimitive;
+
+        let fields = crate::signature::packet::parse_signature(signature)
+            .ok_or(RPMError::NoSignatureFound)?;
+        Ok(Self {
+            tag,
+            key_id: fields.key_id,
+            fingerprint: fields.fingerprint,
+            public_key_algorithm: fields
+                .public_key_algorithm
+                .and_then(PublicKeyAlgorithm::from_u8),
+            hash_algorithm: fields.hash_algorithm.and_then(FileDigestAlgorithm::from_u8),
+            creation_time: fields
+                .creation_time
+                .and_then(|time| chrono::Utc.timestamp_opt(time as i64, 0).single()),
+        })
+    }
+}
+
+/// Result of [`RPMPackage::verify`](super::RPMPackage::verify).
+///
+/// Lists every signature and digest of the signature header.
+#[derive(Debug)]
+pub struct VerificationReport {
+    /// the signatures present in the package, in the order rpm checks them
+    pub signatures: Vec<SignatureCheck>,
+    pub digests: DigestReport,
+}
+
+impl VerificationReport {
+    /// At least one signature verified, none failed and no digest mismatches.
+    pub fn is_ok(&self) -> bool {
+        self.signatures
+            .iter()
+            .any(|signature| signature.status.is_verified())
+            && !self
+                .signatures
+                .iter()
+                .any(|signature| signature.status.is_failed())
+            && !self.digests.all().iter().any(|status| status.is_mismatch())
+    }
+
+    /// Key IDs of all signatures which verified.
+    pub fn verified_key_ids(&self) -> impl Iterator<Item = &str> {
+        self.signatures
+            .iter()
+            .filter(|signature| signature.status.is_verified())
+            .filter_map(|signature| signature.key_id.as_deref())
+    }
+}
+
+/// Digest check of a single file of the payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigestCheck {
+    /// path of the file as listed in the header
+    pub path: PathBuf,
+    /// [`DigestStatus::NotPresent`] for entries without a digest,
+    /// such as directories or symlinks
+    pub status: DigestStatus,
+}
+
+/// Result of [`RPMPackage::verify_file_digests`](super::RPMPackage::verify_file_digests).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileDigestReport {
+    /// files found in both the header and the payload, in header order
+    pub files: Vec<FileDigestCheck>,
+    /// files listed in the header but absent from the payload, ghost files excluded
+    pub missing: Vec<PathBuf>,
+    /// files present in the payload but not listed in the header
+    pub extra: Vec<PathBuf>,
+}
+
+impl FileDigestReport {
+    /// All files whose content does not match the digest in the header.
+    pub fn mismatches(&self) -> impl Iterator<Item = &FileDigestCheck> {
+        self.files.iter().filter(|file| file.status.is_mismatch())
+    }
+
+    /// No file is missing, extra or mismatching.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatches().next().is_none()
+    }
+}
+
+// see https://man7.org/linux/man-pages/man7/inode.7.html section "The file type and mode"
+const FILE_TYPE_BIT_MASK: u16 = 0o170000;
+const REGULAR_FILE_TYPE: u16 = 0o100000;
+const SYMLINK_FILE_TYPE: u16 = 0o120000;
+
+/// A file as described by the header.
+struct HeaderFile<'a> {
+    path: PathBuf,
+    digest: &'a str,
+    mode: u16,
+    flags: i32,
+    size: u64,
+}
+
+impl HeaderFile<'_> {
+    fn is_regular(&self) -> bool {
+        self.mode & FILE_TYPE_BIT_MASK == REGULAR_FILE_TYPE
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.mode & FILE_TYPE_BIT_MASK == SYMLINK_FILE_TYPE
+    }
+}
+
+fn header_files(header: &Header<IndexTag>) -> Result<Vec<HeaderFile<'_>>, RPMError> {
+    let paths = match header.get_file_paths() {
+        Ok(paths) => paths,
+        // packages without files do not carry any of the file tags
+        Err(RPMError::TagNotFound(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let digests = header.get_file_checksums()?;
+    let modes = header.get_entry_i16_array_data(IndexTag::RPMTAG_FILEMODES)?;
+    let flags = header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEFLAGS)?;
+    let sizes = header.get_file_sizes()?;
+
+    Ok(
+        itertools::multizip((paths.into_iter(), digests, modes, flags, sizes))
+            .map(|(path, digest, mode, flags, size)| HeaderFile {
+                path,
+                digest,
+                mode: mode as u16,
+                flags,
+                size,
+            })
+            .collect(),
+    )
+}
+
+/// For every file the index of the file carrying the content.
+///
+/// Hardlinked files share a single content, which rpm only
+/// stores with the last of them within the payload.
+fn hardlink_content_indices(header: &Header<IndexTag>, files: &[HeaderFile]) -> Vec<usize> {
+    let devices = header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEDEVICES);
+    let inodes = header.get_entry_i32_array_data(IndexTag::RPMTAG_FILEINODES);
+    let (devices, inodes) = match (devices, inodes) {
+        (Ok(devices), Ok(inodes))
+            if devices.len() == files.len() && inodes.len() == files.len() =>
+        {
+            (devices, inodes)
+        }
+        _ => return (0..files.len()).collect(),
+    };
+
+    let mut last_link = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        if file.is_regular() {
+            last_link.insert((devices[index], inodes[index]), index);
+        }
+    }
+    files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            if file.is_regular() {
+                last_link[&(devices[index], inodes[index])]
+            } else {
+                index
+            }
+        })
+        .collect()
+}
+
+/// Convert the name of an archive entry to the path used in the header.
+fn archive_path(name: &str) -> PathBuf {
+    // rpm stores `./usr/bin/foo` for `/usr/bin/foo`
+    let name = name.strip_prefix('.').unwrap_or(name);
+    Path::new("/").join(name.trim_start_matches('/'))
+}
+
+/// Wrap `payload` with a decompressor matching the compressor recorded in `header`.
+pub(crate) fn decompress_payload<'a>(
+    header: &Header<IndexTag>,
+    payload: &'a [u8],
+) -> Result<Box<dyn Read + 'a>, RPMError> {
+    let compressor_name = match header.get_payload_compressor() {
+        Ok(compressor_name) => compressor_name,
+        // uncompressed packages built by this crate do not record a compressor
+        Err(_) if payload.starts_with(b"0707") => "none",
+        // rpm falls back to gzip, if the compressor is not recorded
+        Err(_) => "gzip",
+    };
+    compressor::decompress_stream(compressor_name, payload)
+}
+
+/// Decompress the payload and compare the content of each file to its digest in the header.
+pub(crate) fn verify_file_digests(
+    header: &Header<IndexTag>,
+    payload: &[u8],
+) -> Result<FileDigestReport, RPMError> {
+    let files = header_files(header)?;
+    let content_indices = hardlink_content_indices(header, &files);
+    // rpm does not encode it, if it is the default md5
+    let algorithm = header.get_file_digest_algorithm().unwrap_or_default();
+
+    // stripped archive entries only carry the file index, not the size
+    let stripped_sizes = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            if (file.is_regular() && content_indices[index] == index) || file.is_symlink() {
+                file.size
+            } else {
+                0
+            }
+        })
+        .collect::<Vec<u64>>();
+    let by_path = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (file.path.as_path(), index))
+        .collect::<HashMap<&Path, usize>>();
+
+    let mut statuses: Vec<Option<DigestStatus>> = vec![None; files.len()];
+    let mut extra = Vec::new();
+
+    let archive = decompress_payload(header, payload)?;
+    payload::for_each_entry(archive, &stripped_sizes, |name, content| {
+        let index = match name {
+            ArchiveEntryName::Index(index) => *index as usize,
+            ArchiveEntryName::Path(name) => {
+                let path = archive_path(name);
+                match by_path.get(path.as_path()) {
+                    Some(index) => *index,
+                    None => {
+                        extra.push(path);
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        let file = &files[index];
+        let status = if file.digest.is_empty() || content_indices[index] != index {
+            // hardlinks without content are resolved once all files are seen
+            DigestStatus::NotPresent
+        } else {
+            DigestStatus::compare(file.digest, digest_hex(algorithm, content)?)
+        };
+        statuses[index] = Some(status);
+        Ok(())
+    })?;
+
+    let mut report = FileDigestReport {
+        extra,
+        ..Default::default()
+    };
+    for (index, file) in files.iter().enumerate() {
+        match &statuses[index] {
+            Some(status) => {
+                let content_index = content_indices[index];
+                let status = if content_index != index && !file.digest.is_empty() {
+                    statuses[content_index]
+                        .clone()
+                        .unwrap_or(DigestStatus::NotPresent)
+                } else {
+                    status.clone()
+                };
+                report.files.push(FileDigestCheck {
+                    path: file.path.clone(),
+                    status,
+                });
+            }
+            None if file.flags & RPMFILE_GHOST != 0 => {}
+            None => report.missing.push(file.path.clone()),
+        }
+    }
+    Ok(report)
+}
+
+/// Hash all of `input` using `algorithm` and return the hex encoded digest.
+pub(crate) fn digest_hex<R: Read>(
+    algorithm: FileDigestAlgorithm,
+    input: R,
+) -> Result<String, RPMError> {
+    match algorithm {
+        FileDigestAlgorithm::Md5 => hash_reader::<md5::Md5, R>(input),
+        FileDigestAlgorithm::Sha1 => {
+            // the `sha1` crate does not implement the `Digest` trait
+            let mut hasher = sha1::Sha1::new();
+            read_chunks(input, |chunk| hasher.update(chunk))?;
+            Ok(hasher.digest().to_string())
+        }
+        FileDigestAlgorithm::Sha2_224 => hash_reader::<sha2::Sha224, R>(input),
+        FileDigestAlgorithm::Sha2_256 => hash_reader::<sha2::Sha256, R>(input),
+        FileDigestAlgorithm::Sha2_384 => hash_reader::<sha2::Sha384, R>(input),
+        FileDigestAlgorithm::Sha2_512 => hash_reader::<sha2::Sha512, R>(input),
+        digest_algo => Err(RPMError::UnsupportedFileDigestAlgorithm(digest_algo)),
+    }
+}
+
+fn hash_reader<D: sha2::Digest, R: Read>(input: R) -> Result<String, RPMError> {
+    let mut hasher = D::new();
+    read_chunks(input, |chunk| hasher.update(chunk))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn read_chunks<R: Read, F: FnMut(&[u8])>(mut input: R, mut f: F) -> Result<(), RPMError> {
+    // avoid loading it into memory all at once
+    // since the content could be multiple 100s of MBs
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = input.read(&mut buf[..])?;
+        if n == 0 {
+            return Ok(());
+        }
+        f(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn digest_status_compare() {
+        assert_eq!(
+            DigestStatus::compare("ABCD", "abcd".to_string()),
+            DigestStatus::Verified
+        );
+        assert!(DigestStatus::compare("abcd", "abce".to_string()).is_mismatch());
+
+        let report = DigestReport {
+            header_sha1: DigestStatus::NotPresent,
+            header_sha256: DigestStatus::Verified,
+            header_and_payload_md5: DigestStatus::NotPresent,
+            payload: DigestStatus::Verified,
+            payload_uncompressed: DigestStatus::NotPresent,
+        };
+        assert!(report.is_ok());
+
+        let report = DigestReport {
+            payload: DigestStatus::compare("00", "01".to_string()),
+            ..report
+        };
+        assert!(!report.is_ok());
+    }
+}
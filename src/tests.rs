@@ -1,4 +1,6 @@
 use super::*;
+use std::io::Read;
+use std::str::FromStr;
 
 fn test_rpm_file_path() -> std::path::PathBuf {
     let mut rpm_path = cargo_manifest_dir();
@@ -16,6 +18,24 @@ fn cargo_manifest_dir() -> std::path::PathBuf {
     std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
 }
 
+/// A package holding a single config file.
+fn test_package_builder() -> Result<RPMBuilder, RPMError> {
+    RPMBuilder::new("test", "1.0.0", "MIT", "x86_64", "some package").with_file(
+        "./test_assets/awesome.toml",
+        RPMFileOptions::new("/etc/awesome/config.toml"),
+    )
+}
+
+/// Signer and verifier for the RSA test key.
+#[cfg(feature = "signature-pgp")]
+fn test_signer_and_verifier() -> Result<(signature::pgp::Signer, signature::pgp::Verifier), RPMError>
+{
+    let (signing_key, verification_key) = signature::pgp::test::load_asc_keys();
+    let signer = signature::pgp::Signer::load_from_asc_bytes(signing_key.as_ref())?;
+    let verifier = signature::pgp::Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+    Ok((signer, verifier))
+}
+
 #[test]
 fn test_rpm_file_signatures() -> Result<(), Box<dyn std::error::Error>> {
     let rpm_file_path = file_signatures_test_rpm_file_path();
@@ -287,6 +307,120 @@ fn test_rpm_header() -> Result<(), Box<dyn std::error::Error>> {
     test_rpm_header_base(package)
 }
 
+fn test_asset_packages() -> Vec<std::path::PathBuf> {
+    let mut packages = std::fs::read_dir(cargo_manifest_dir().join("test_assets"))
+        .expect("should be able to list the test assets")
+        .map(|entry| {
+            entry
+                .expect("should be able to list the test assets")
+                .path()
+        })
+        .filter(|path| path.extension() == Some("rpm".as_ref()))
+        .collect::<Vec<_>>();
+    packages.sort();
+    assert!(!packages.is_empty());
+    packages
+}
+
+#[test]
+fn test_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    for path in test_asset_packages() {
+        let original = std::fs::read(&path)?;
+        let mut package = RPMPackage::parse(&mut original.as_slice())?;
+        let mut written = Vec::new();
+        package.write(&mut written)?;
+        assert!(
+            original == written,
+            "{} does not round-trip",
+            path.display()
+        );
+
+        // rebuilding the headers from their entries, as modifying them does, keeps
+        // the layout of rpm and thereby all bytes, not only the ones within the region
+        package
+            .metadata
+            .header
+            .modify_entries(IndexTag::RPMTAG_HEADERIMMUTABLE, |_| {});
+        package
+            .metadata
+            .signature
+            .modify_entries(IndexSignatureTag::HEADER_SIGNATURES, |_| {});
+        assert_eq!(None, package.metadata.header.raw);
+        let mut written = Vec::new();
+        package.write(&mut written)?;
+        assert!(
+            original == written,
+            "{} is not rebuilt as is",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_round_trip_async() -> Result<(), Box<dyn std::error::Error>> {
+    for path in test_asset_packages() {
+        let original = tokio::fs::read(&path).await?;
+        let package = RPMPackage::parse_async(&mut original.as_slice()).await?;
+        let mut written = Vec::new();
+        package.write_async(&mut written).await?;
+        assert!(
+            original == written,
+            "{} does not round-trip",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_generic_tag_access() -> Result<(), Box<dyn std::error::Error>> {
+    let rpm_file = std::fs::File::open(test_rpm_file_path())?;
+    let package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let header = &package.metadata.header;
+
+    assert_eq!(
+        Some("x86-01.bsys.centos.org"),
+        header
+            .get(IndexTag::RPMTAG_BUILDHOST)
+            .and_then(IndexData::as_str)
+    );
+    assert_eq!(
+        Some(&IndexData::I18NString(vec![
+            "Development/Libraries".to_string()
+        ])),
+        header.get(IndexTag::RPMTAG_GROUP)
+    );
+    assert_eq!(None, header.get(IndexTag::RPMTAG_MODULARITYLABEL));
+    let mut header_bytes = Vec::new();
+    header.write(&mut header_bytes)?;
+    assert_eq!(
+        Some((header_bytes.len() + package.content.len()) as i32),
+        package
+            .metadata
+            .signature
+            .get(IndexSignatureTag::RPMSIGTAG_SIZE)
+            .and_then(IndexData::as_i32)
+    );
+
+    let entries = header.entries().collect::<Vec<_>>();
+    assert_eq!(56, entries.len());
+    assert_eq!(IndexTag::RPMTAG_HEADERIMMUTABLE, entries[0].tag());
+    assert_eq!(
+        (IndexTag::RPMTAG_NAME, 6, 1),
+        (
+            entries[2].tag(),
+            entries[2].data().to_u32(),
+            entries[2].num_items()
+        )
+    );
+    for entry in entries {
+        assert_eq!(entry.data().num_items(), entry.num_items());
+        assert_eq!(Some(entry.data()), header.get(entry.tag()));
+    }
+    Ok(())
+}
+
 #[test]
 fn test_region_tag() -> Result<(), Box<dyn std::error::Error>> {
     let region_entry = Header::create_region_tag(IndexSignatureTag::HEADER_SIGNATURES, 2, 400);
@@ -297,11 +431,1017 @@ fn test_region_tag() -> Result<(), Box<dyn std::error::Error>> {
 
     let data = possible_binary.unwrap();
 
-    let (_, entry) = IndexEntry::<IndexSignatureTag>::parse(&data)?;
+    let (_, entry) = RawIndexEntry::parse::<IndexSignatureTag>(&data)?;
 
-    assert_eq!(entry.tag, IndexSignatureTag::HEADER_SIGNATURES);
+    assert_eq!(entry.tag, IndexSignatureTag::HEADER_SIGNATURES as u32);
     assert_eq!(entry.data.to_u32(), IndexData::Bin(Vec::new()).to_u32());
     assert_eq!(-48, entry.offset);
 
     Ok(())
 }
+
+#[test]
+fn test_payload_size() -> Result<(), Box<dyn std::error::Error>> {
+    let pkg = test_package_builder()?.build()?;
+
+    // without compression, the payload is the plain cpio archive
+    let payload_size = pkg
+        .metadata
+        .signature
+        .get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)?;
+    assert_eq!(pkg.content.len(), payload_size as usize);
+
+    let file_sizes = pkg
+        .metadata
+        .header
+        .get_entry_i32_array_data(IndexTag::RPMTAG_FILESIZES)?;
+    assert_eq!(
+        vec![std::fs::metadata("./test_assets/awesome.toml")?.len() as i32],
+        file_sizes
+    );
+    Ok(())
+}
+
+#[test]
+fn test_large_files() -> Result<(), Box<dyn std::error::Error>> {
+    // stands in for 4 GiB, which would not fit into memory
+    let threshold = std::fs::metadata("./test_assets/awesome.toml")?.len() - 1;
+    LARGE_SIZE_THRESHOLD.with(|large_size_threshold| large_size_threshold.set(threshold));
+    let pkg = test_package_builder()?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome"),
+        )?
+        .build()?;
+
+    let mut out = Vec::new();
+    pkg.write(&mut out)?;
+    let parsed = RPMPackage::parse(&mut std::io::Cursor::new(out))?;
+    let header = &parsed.metadata.header;
+
+    // the whole payload is in the stripped format, referencing the files by index
+    assert!(parsed.content.starts_with(b"07070X00000000"));
+    assert!(header
+        .find_entry_or_err(&IndexTag::RPMTAG_FILESIZES)
+        .is_err());
+    assert!(header.find_entry_or_err(&IndexTag::RPMTAG_SIZE).is_err());
+    let file_sizes = header.get_entry_i64_array_data(IndexTag::RPMTAG_LONGFILESIZES)?;
+    assert_eq!(
+        vec![
+            threshold as i64 + 1,
+            std::fs::metadata("./test_assets/awesome.py")?.len() as i64,
+        ],
+        file_sizes
+    );
+    assert_eq!(
+        file_sizes.iter().sum::<i64>(),
+        header.get_entry_i64_data(IndexTag::RPMTAG_LONGSIZE)?
+    );
+    assert!(header
+        .get_entry_string_array_data(IndexTag::RPMTAG_REQUIRENAME)?
+        .iter()
+        .any(|name| name == "rpmlib(LargeFiles)"));
+
+    let report = parsed.verify_file_digests()?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(2, report.files.len());
+
+    // the package and payload sizes are stored in the 64 bit tags as well
+    let signature = &parsed.metadata.signature;
+    let mut header_bytes = Vec::new();
+    parsed.metadata.header.write(&mut header_bytes)?;
+    assert_eq!(
+        (header_bytes.len() + parsed.content.len()) as i64,
+        signature.get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGSIGSIZE)?
+    );
+    assert_eq!(
+        parsed.content.len() as i64,
+        signature.get_entry_i64_data(IndexSignatureTag::RPMSIGTAG_LONGARCHIVESIZE)?
+    );
+    assert!(signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SIZE)
+        .is_err());
+    assert!(signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)
+        .is_err());
+    assert!(parsed.verify_digests()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_payload_and_header_digests() -> Result<(), Box<dyn std::error::Error>> {
+    use sha2::Digest;
+
+    let pkg = test_package_builder()?
+        .compression(Compressor::from_str("gzip")?)
+        .legacy_digests(false)
+        .build()?;
+
+    let mut header_bytes = Vec::new();
+    pkg.metadata.header.write(&mut header_bytes)?;
+    assert_eq!(
+        hex::encode(sha2::Sha256::digest(&header_bytes)),
+        pkg.metadata
+            .signature
+            .get_entry_string_data(IndexSignatureTag::RPMSIGTAG_SHA256)?
+    );
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_MD5)
+        .is_err());
+    assert!(pkg
+        .metadata
+        .signature
+        .find_entry_or_err(&IndexSignatureTag::RPMSIGTAG_SHA1)
+        .is_err());
+
+    assert_eq!(
+        hex::encode(sha2::Sha256::digest(&pkg.content)),
+        pkg.metadata.header.get_payload_digest()?
+    );
+    assert!(matches!(
+        pkg.metadata.header.get_payload_digest_algorithm()?,
+        FileDigestAlgorithm::Sha2_256
+    ));
+
+    let mut archive = Vec::new();
+    libflate::gzip::Decoder::new(pkg.content.as_slice())?.read_to_end(&mut archive)?;
+    assert_eq!(
+        hex::encode(sha2::Sha256::digest(&archive)),
+        pkg.metadata.header.get_payload_digest_alt()?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_verify_digests() -> Result<(), Box<dyn std::error::Error>> {
+    let rpm_file = std::fs::File::open(test_rpm_file_path())?;
+    let mut package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+
+    let report = package.verify_digests()?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(DigestStatus::Verified, report.header_sha1);
+    assert_eq!(DigestStatus::Verified, report.header_and_payload_md5);
+    assert_eq!(DigestStatus::NotPresent, report.header_sha256);
+
+    // flip a bit in the payload
+    package.content[42] ^= 0x01;
+    let report = package.verify_digests()?;
+    assert!(!report.is_ok());
+    assert_eq!(DigestStatus::Verified, report.header_sha1);
+    assert!(report.header_and_payload_md5.is_mismatch());
+
+    let built = test_package_builder()?.build()?;
+    let report = built.verify_digests()?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(DigestStatus::Verified, report.header_sha256);
+    assert_eq!(DigestStatus::Verified, report.payload);
+    assert_eq!(DigestStatus::Verified, report.payload_uncompressed);
+
+    // the alternative payload digest spans the decompressed payload
+    let mut built = test_package_builder()?
+        .compression(Compressor::from_str("gzip")?)
+        .build()?;
+    let report = built.verify_digests()?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(DigestStatus::Verified, report.payload);
+    assert_eq!(DigestStatus::Verified, report.payload_uncompressed);
+
+    // an unknown payload digest algorithm is an error rather than a mismatch
+    built
+        .metadata
+        .header
+        .index_entries
+        .iter_mut()
+        .find(|entry| entry.tag == IndexTag::RPMTAG_PAYLOADDIGESTALGO)
+        .expect("payload digest algorithm")
+        .data = IndexData::Int32(vec![999]);
+    assert!(matches!(
+        built.verify_digests(),
+        Err(RPMError::InvalidTagValueEnumVariant { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_verify_file_digests() -> Result<(), Box<dyn std::error::Error>> {
+    // covers xz and zstd compressed payloads
+    for asset in &[
+        "389-ds-base-devel-1.3.8.4-15.el7.x86_64.rpm",
+        "ima_signed.rpm",
+        "monkeysphere-0.37-1.el7.noarch.rpm",
+        "rpm-sign-4.15.1-1.fc31.x86_64.rpm",
+    ] {
+        let rpm_file = std::fs::File::open(cargo_manifest_dir().join("test_assets").join(asset))?;
+        let package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+        let report = package.verify_file_digests()?;
+        assert!(report.is_ok(), "{}: {:?}", asset, report);
+        assert!(report.files.iter().any(|file| file.status.is_verified()));
+    }
+
+    let mut built = test_package_builder()?
+        .with_file(
+            "./test_assets/awesome.py",
+            RPMFileOptions::new("/usr/bin/awesome"),
+        )?
+        .build()?;
+    let report = built.verify_file_digests()?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(2, report.files.len());
+
+    // without compression, the file content can be altered directly
+    let content = std::fs::read("./test_assets/awesome.toml")?;
+    let offset = built
+        .content
+        .windows(content.len())
+        .position(|window| window == content.as_slice())
+        .expect("file content is part of the payload");
+    built.content[offset] ^= 0x01;
+    let report = built.verify_file_digests()?;
+    assert!(!report.is_ok());
+    let mismatches = report.mismatches().collect::<Vec<_>>();
+    assert_eq!(1, mismatches.len());
+    assert_eq!(
+        std::path::Path::new("/etc/awesome/config.toml"),
+        mismatches[0].path
+    );
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_verification_report() -> Result<(), Box<dyn std::error::Error>> {
+    let (signer, verifier) = test_signer_and_verifier()?;
+
+    let mut package = test_package_builder()?.build_and_sign(&signer)?;
+
+    let report = package.verify(&verifier)?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(
+        vec![
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP
+        ],
+        report
+            .signatures
+            .iter()
+            .map(|check| check.tag)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["cfd331925ab27f39"; 2],
+        report.verified_key_ids().collect::<Vec<_>>()
+    );
+    assert_eq!(DigestStatus::Verified, report.digests.header_sha256);
+    package.verify_signature(&verifier)?;
+
+    // the header only signature still holds, the one spanning the payload does not
+    let last = package.content.len() - 1;
+    package.content[last] ^= 0x01;
+    let report = package.verify(&verifier)?;
+    assert!(!report.is_ok());
+    assert!(report.signatures[0].status.is_verified());
+    assert!(report.signatures[1].status.is_failed());
+    assert!(report.digests.payload.is_mismatch());
+    assert!(package.verify_signature(&verifier).is_err());
+
+    // signed by the CentOS 7 key, which is unknown to the verifier
+    let rpm_file = std::fs::File::open(test_rpm_file_path())?;
+    let package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let report = package.verify(&verifier)?;
+    assert!(!report.is_ok());
+    assert!(report.digests.is_ok());
+    assert!(!report.signatures.is_empty());
+    for check in report.signatures.iter() {
+        assert!(check.status.is_failed());
+        assert_eq!(Some("24c6a8a7f4a80eb5"), check.key_id.as_deref());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_signature_info() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeZone;
+
+    let rpm_file = std::fs::File::open(test_rpm_file_path())?;
+    let package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let signatures = package.signatures()?;
+    assert_eq!(
+        vec![
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP
+        ],
+        signatures.iter().map(|info| info.tag).collect::<Vec<_>>()
+    );
+    for info in signatures {
+        assert_eq!(Some("24c6a8a7f4a80eb5"), info.key_id.as_deref());
+        assert_eq!(None, info.fingerprint);
+        assert_eq!(Some(PublicKeyAlgorithm::RSA), info.public_key_algorithm);
+        assert_eq!(Some(FileDigestAlgorithm::Sha2_256), info.hash_algorithm);
+        assert_eq!(
+            Some(chrono::Utc.timestamp_opt(1_542_032_475, 0).unwrap()),
+            info.creation_time
+        );
+    }
+
+    let creation_time = chrono::Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+    let (signer, _) = test_signer_and_verifier()?;
+    let signer = signer
+        .with_creation_time(creation_time)
+        .with_hash_algorithm(::pgp::crypto::hash::HashAlgorithm::SHA2_512)?;
+    let package = test_package_builder()?.build_and_sign(&signer)?;
+    let signatures = package.signatures()?;
+    assert_eq!(2, signatures.len());
+    for info in signatures {
+        assert_eq!(Some("cfd331925ab27f39"), info.key_id.as_deref());
+        assert_eq!(Some(PublicKeyAlgorithm::RSA), info.public_key_algorithm);
+        assert_eq!(Some(FileDigestAlgorithm::Sha2_512), info.hash_algorithm);
+        assert_eq!(Some(creation_time), info.creation_time);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_verify_dsa() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::pgp::Verifier;
+
+    // signed by `gpg` using `test_assets/dsa_secret_key.asc`, as older distributions did
+    let rpm_file = std::fs::File::open("./test_assets/dsa-signed-1.0.0-1.noarch.rpm")?;
+    let mut package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let verifier =
+        Verifier::load_from_asc_bytes(include_bytes!("../test_assets/dsa_public_key.asc"))?;
+
+    let signatures = package.signatures()?;
+    assert_eq!(
+        vec![
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG
+        ],
+        signatures.iter().map(|info| info.tag).collect::<Vec<_>>()
+    );
+    for info in signatures {
+        assert_eq!(Some("637fce9e2818523c"), info.key_id.as_deref());
+        assert_eq!(Some(PublicKeyAlgorithm::DSA), info.public_key_algorithm);
+    }
+
+    package.verify_signature(&verifier)?;
+    let report = package.verify(&verifier)?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(
+        vec![
+            (IndexSignatureTag::RPMSIGTAG_DSA, true),
+            (IndexSignatureTag::RPMSIGTAG_GPG, true)
+        ],
+        report
+            .signatures
+            .iter()
+            .map(|check| (check.tag, check.status.is_verified()))
+            .collect::<Vec<_>>()
+    );
+
+    let (_, rsa_verification_key) = signature::pgp::test::load_asc_keys();
+    let rsa_verifier = Verifier::load_from_asc_bytes(rsa_verification_key.as_ref())?;
+    assert!(package.verify_signature(&rsa_verifier).is_err());
+
+    let last = package.content.len() - 1;
+    package.content[last] ^= 0x01;
+    let report = package.verify(&verifier)?;
+    assert!(report.signatures[0].status.is_verified());
+    assert!(report.signatures[1].status.is_failed());
+    assert!(package.verify_signature(&verifier).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_openpgp_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::pgp::{Signer, Verifier};
+
+    let (rsa_signer, rsa_verifier) = test_signer_and_verifier()?;
+    let (signing_key, verification_key) = signature::pgp::test::load_ed25519_asc_keys();
+    let ed25519_signer = Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_algorithm::<signature::algorithm::EdDSA>();
+    let ed25519_verifier = Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    // during the transition from the RSA to the Ed25519 key, both sign
+    let mut package = test_package_builder()?.build_and_sign(&rsa_signer)?;
+    package.add_openpgp_signature(&rsa_signer)?;
+    package.add_openpgp_signature(&ed25519_signer)?;
+
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    let mut package = RPMPackage::parse(&mut buffer.as_slice())?;
+    assert_eq!(
+        2,
+        package.metadata.signature.get_openpgp_signatures()?.len()
+    );
+    assert_eq!(
+        vec![
+            (IndexSignatureTag::RPMSIGTAG_RSA, PublicKeyAlgorithm::RSA),
+            (IndexSignatureTag::RPMSIGTAG_PGP, PublicKeyAlgorithm::RSA),
+            (
+                IndexSignatureTag::RPMSIGTAG_OPENPGP,
+                PublicKeyAlgorithm::RSA
+            ),
+            (
+                IndexSignatureTag::RPMSIGTAG_OPENPGP,
+                PublicKeyAlgorithm::EdDSA
+            ),
+        ],
+        package
+            .signatures()?
+            .into_iter()
+            .map(|info| (info.tag, info.public_key_algorithm.unwrap()))
+            .collect::<Vec<_>>()
+    );
+
+    let report = package.verify(&rsa_verifier)?;
+    assert_eq!(3, report.verified_key_ids().count());
+    package.verify_signature(&rsa_verifier)?;
+
+    // only the header is signed by the new key, the payload digest covers the rest
+    let report = package.verify(&ed25519_verifier)?;
+    assert_eq!(
+        vec!["42453bf1d4398008"],
+        report.verified_key_ids().collect::<Vec<_>>()
+    );
+    package.verify_signature(&ed25519_verifier)?;
+
+    let last = package.content.len() - 1;
+    package.content[last] ^= 0x01;
+    assert!(package.verify_signature(&ed25519_verifier).is_err());
+    assert!(package.verify_signature(&rsa_verifier).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_resign_keeps_other_entries() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::pgp::{Signer, Verifier};
+
+    let (rsa_signer, rsa_verifier) = test_signer_and_verifier()?;
+    let (signing_key, verification_key) = signature::pgp::test::load_second_asc_keys();
+    let second_signer = Signer::load_from_asc_bytes(signing_key.as_ref())?;
+    let second_verifier = Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+    let (signing_key, verification_key) = signature::pgp::test::load_ed25519_asc_keys();
+    let ed25519_signer = Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_algorithm::<signature::algorithm::EdDSA>();
+    let ed25519_verifier = Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    let rpm_file = std::fs::File::open(file_signatures_test_rpm_file_path())?;
+    let mut package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let ima_signatures = package
+        .metadata
+        .signature
+        .get_file_ima_signatures()?
+        .to_vec();
+    let payload_size = package
+        .metadata
+        .signature
+        .get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)?;
+
+    // staging signature, replaced by the production one
+    package.sign(&second_signer)?;
+    package.sign(&rsa_signer)?;
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    let mut package = RPMPackage::parse(&mut buffer.as_slice())?;
+    let signature = &package.metadata.signature;
+    assert_eq!(ima_signatures, signature.get_file_ima_signatures()?);
+    assert_eq!(
+        payload_size,
+        signature.get_entry_i32_data(IndexSignatureTag::RPMSIGTAG_PAYLOADSIZE)?
+    );
+    package.verify_signature(&rsa_verifier)?;
+    assert!(package.verify_signature(&second_verifier).is_err());
+
+    package.remove_signatures();
+    assert!(package.signatures()?.is_empty());
+    assert!(package.verify_digests()?.is_ok());
+    assert_eq!(
+        ima_signatures,
+        package.metadata.signature.get_file_ima_signatures()?
+    );
+    assert!(matches!(
+        package.verify_signature(&rsa_verifier),
+        Err(RPMError::TagNotFound(_))
+    ));
+
+    /// counts the signatures made by the wrapped signer
+    #[derive(Debug)]
+    struct CountingSigner<S> {
+        signer: S,
+        count: std::cell::Cell<usize>,
+    }
+
+    impl<S> signature::Signing<signature::algorithm::RSA> for CountingSigner<S>
+    where
+        S: signature::Signing<signature::algorithm::RSA, Signature = Vec<u8>>,
+    {
+        type Signature = Vec<u8>;
+        fn sign<R: Read>(&self, data: R) -> Result<Self::Signature, RPMError> {
+            self.count.set(self.count.get() + 1);
+            self.signer.sign(data)
+        }
+    }
+
+    // the slots for RSA signatures are taken by the first one
+    package.add_signature(&rsa_signer)?;
+    package.add_signature(&ed25519_signer)?;
+    let counting_signer = CountingSigner {
+        signer: &second_signer,
+        count: std::cell::Cell::new(0),
+    };
+    package.add_signature(&counting_signer)?;
+    // without a free slot, the header and payload signature is not even made
+    assert_eq!(1, counting_signer.count.get());
+    assert_eq!(
+        vec![
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_PGP,
+            IndexSignatureTag::RPMSIGTAG_DSA,
+            IndexSignatureTag::RPMSIGTAG_GPG,
+            IndexSignatureTag::RPMSIGTAG_OPENPGP,
+        ],
+        package
+            .signatures()?
+            .iter()
+            .map(|info| info.tag)
+            .collect::<Vec<_>>()
+    );
+    for verifier in &[rsa_verifier, second_verifier, ed25519_verifier] {
+        package.verify_signature(verifier)?;
+    }
+    assert!(package.verify_digests()?.is_ok());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_resign_in_place() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::pgp::{Signer, Verifier};
+
+    let (rsa_signer, rsa_verifier) = test_signer_and_verifier()?;
+    let (signing_key, verification_key) = signature::pgp::test::load_ed25519_asc_keys();
+    let ed25519_signer = Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_algorithm::<signature::algorithm::EdDSA>();
+    let ed25519_verifier = Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    let package = test_package_builder()?.build_and_sign(&rsa_signer)?;
+    let signature_len = package.metadata.signature.written_len();
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    let original = buffer.clone();
+
+    let mut file = std::io::Cursor::new(buffer);
+    RPMPackage::resign_in_place(&mut file, &ed25519_signer)?;
+    let buffer = file.into_inner();
+    assert_eq!(original.len(), buffer.len());
+    let unchanged = LEAD_SIZE + signature_len;
+    assert_eq!(original[..LEAD_SIZE], buffer[..LEAD_SIZE]);
+    assert_eq!(original[unchanged..], buffer[unchanged..]);
+
+    let package = RPMPackage::parse(&mut buffer.as_slice())?;
+    package.verify_signature(&ed25519_verifier)?;
+    assert!(package.verify_signature(&rsa_verifier).is_err());
+    assert!(package.verify_digests()?.is_ok());
+
+    // IMA signatures are untouched as well
+    let original = std::fs::read(file_signatures_test_rpm_file_path())?;
+    let mut file = std::io::Cursor::new(original.clone());
+    RPMPackage::resign_in_place(&mut file, &rsa_signer)?;
+    let buffer = file.into_inner();
+    assert_eq!(original.len(), buffer.len());
+    let package = RPMPackage::parse(&mut buffer.as_slice())?;
+    package.verify_signature(&rsa_verifier)?;
+    assert_eq!(
+        RPMPackage::parse(&mut original.as_slice())?
+            .metadata
+            .signature
+            .get_file_ima_signatures()?,
+        package.metadata.signature.get_file_ima_signatures()?
+    );
+
+    // without reserved space, the larger signature header does not fit
+    let original = std::fs::read(test_rpm_file_path())?;
+    let mut file = std::io::Cursor::new(original.clone());
+    assert!(matches!(
+        RPMPackage::resign_in_place(&mut file, &ed25519_signer),
+        Err(RPMError::InsufficientReservedSpace { .. })
+    ));
+    assert_eq!(original, file.into_inner());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_resign_in_place_unknown_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let (signer, verifier) = test_signer_and_verifier()?;
+    // a tag of a newer rpm version, sorted after `RPMSIGTAG_RESERVEDSPACE`
+    let unknown_tag = 1100u32;
+    assert!(<IndexSignatureTag as num_traits::FromPrimitive>::from_u32(unknown_tag).is_none());
+
+    let package = test_package_builder()?.build_and_sign(&signer)?;
+    let md5 = package
+        .metadata
+        .signature
+        .get(IndexSignatureTag::RPMSIGTAG_MD5)
+        .and_then(IndexData::as_binary)
+        .map(<[u8]>::to_vec);
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    // turn the entry of `RPMSIGTAG_MD5` into one of the unknown tag
+    let md5_tag = (IndexSignatureTag::RPMSIGTAG_MD5 as u32).to_be_bytes();
+    let index = &mut buffer[LEAD_SIZE + 16..];
+    let entry = index
+        .chunks_exact_mut(16)
+        .find(|entry| entry[..4] == md5_tag)
+        .expect("signature header has an MD5 digest");
+    entry[..4].copy_from_slice(&unknown_tag.to_be_bytes());
+    let original = buffer.clone();
+
+    let mut file = std::io::Cursor::new(buffer);
+    RPMPackage::resign_in_place(&mut file, &signer)?;
+    let buffer = file.into_inner();
+    assert_eq!(original.len(), buffer.len());
+    let header_start = LEAD_SIZE + package.metadata.signature.written_len();
+    assert_eq!(original[header_start..], buffer[header_start..]);
+
+    let package = RPMPackage::parse(&mut buffer.as_slice())?;
+    package.verify_signature(&verifier)?;
+    let signature = &package.metadata.signature;
+    let unknown = signature.unknown_entries().collect::<Vec<_>>();
+    assert_eq!(1, unknown.len());
+    assert_eq!(unknown_tag, unknown[0].tag());
+    assert_eq!(md5.as_deref(), unknown[0].data().as_binary());
+
+    // the unknown entry follows `RPMSIGTAG_RESERVEDSPACE`, whose resizing did not affect it
+    let num_entries = signature.index_header.num_entries as usize;
+    let tags = buffer[LEAD_SIZE + 16..LEAD_SIZE + 16 + 16 * num_entries]
+        .chunks_exact(16)
+        .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        IndexSignatureTag::HEADER_SIGNATURES as u32,
+        tags[0],
+        "{:?}",
+        tags
+    );
+    assert_eq!(Some(&unknown_tag), tags.last());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_detached_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    use pgp::Deserializable;
+    use signature::pgp::Signer;
+
+    let (signer, verifier) = test_signer_and_verifier()?;
+    let (signing_key, _) = signature::pgp::test::load_ed25519_asc_keys();
+    let ed25519_signer = Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_algorithm::<signature::algorithm::EdDSA>();
+
+    let mut package = test_package_builder()?.build_and_sign(&signer)?;
+    package.add_openpgp_signature(&ed25519_signer)?;
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+
+    let detached = package.export_detached_signatures()?;
+    let header = detached.header.as_ref().expect("header signatures");
+    let header_and_payload = detached
+        .header_and_payload
+        .as_ref()
+        .expect("payload signature");
+    let (signatures, _) = pgp::StandaloneSignature::from_string_many(header)?;
+    assert_eq!(2, signatures.count());
+    let (signatures, _) = pgp::StandaloneSignature::from_string_many(header_and_payload)?;
+    assert_eq!(1, signatures.count());
+
+    let mut unsigned = RPMPackage::parse(&mut buffer.as_slice())?;
+    unsigned.remove_signatures();
+    unsigned.attach_detached_signatures(&detached)?;
+    let key_ids = |package: &RPMPackage| -> Result<Vec<Option<String>>, RPMError> {
+        Ok(package
+            .signatures()?
+            .into_iter()
+            .map(|info| info.key_id)
+            .collect())
+    };
+    assert_eq!(key_ids(&package)?, key_ids(&unsigned)?);
+    unsigned.verify_signature(&verifier)?;
+
+    // like the signatures of an offline signing host, which only gets to see the header
+    let mut unsigned = RPMPackage::parse(&mut buffer.as_slice())?;
+    unsigned.remove_signatures();
+    let header_only = DetachedSignatures {
+        header: detached.header.clone(),
+        header_and_payload: None,
+    };
+    unsigned.attach_detached_signatures(&header_only)?;
+    assert_eq!(
+        vec![
+            IndexSignatureTag::RPMSIGTAG_RSA,
+            IndexSignatureTag::RPMSIGTAG_DSA
+        ],
+        unsigned
+            .signatures()?
+            .iter()
+            .map(|info| info.tag)
+            .collect::<Vec<_>>()
+    );
+    unsigned.verify_signature(&verifier)?;
+
+    let mut unsigned = RPMPackage::parse(&mut buffer.as_slice())?;
+    unsigned.remove_signatures();
+    let payload_only = DetachedSignatures {
+        header: None,
+        header_and_payload: detached.header_and_payload.clone(),
+    };
+    assert!(matches!(
+        unsigned.attach_detached_signatures(&payload_only),
+        Err(RPMError::NoSignatureFound)
+    ));
+    assert!(unsigned.signatures()?.is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_sign_header_only() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::Signing;
+
+    let (signer, verifier) = test_signer_and_verifier()?;
+
+    let build = || test_package_builder()?.build_for_signing();
+    let unsigned = build()?;
+    // all the signing service gets to see
+    let header_bytes = unsigned.header_bytes().to_vec();
+    let signature = signer.sign(header_bytes.as_slice())?;
+    let package = unsigned.assemble(&signature)?;
+
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    let package = RPMPackage::parse(&mut buffer.as_slice())?;
+    let mut written_header = Vec::new();
+    package.metadata.header.write(&mut written_header)?;
+    assert_eq!(header_bytes, written_header);
+    assert_eq!(
+        vec![IndexSignatureTag::RPMSIGTAG_RSA],
+        package
+            .signatures()?
+            .iter()
+            .map(|info| info.tag)
+            .collect::<Vec<_>>()
+    );
+    assert!(package.verify_digests()?.is_ok());
+    package.verify_signature(&verifier)?;
+
+    assert!(matches!(
+        build()?.assemble(b"not a signature"),
+        Err(RPMError::NoSignatureFound)
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_verify_non_canonical_header() -> Result<(), Box<dyn std::error::Error>> {
+    let (signer, verifier) = test_signer_and_verifier()?;
+
+    let package = test_package_builder()?.build()?;
+    let header_start = LEAD_SIZE + package.metadata.signature.written_len();
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+
+    // like a package of another tool, with a header which does not round-trip,
+    // as the reserved bytes of its index header are ignored and written as zeros
+    buffer[header_start + 4..header_start + 8].copy_from_slice(&[1, 2, 3, 4]);
+    let mut package = RPMPackage::parse(&mut buffer.as_slice())?;
+    package.sign(&signer)?;
+    let mut signed = Vec::new();
+    package.write(&mut signed)?;
+    assert_eq!([1, 2, 3, 4], signed[header_start + 4..header_start + 8]);
+
+    let package = RPMPackage::parse(&mut signed.as_slice())?;
+    assert!(package.verify_digests()?.is_ok());
+    package.verify_signature(&verifier)?;
+    let mut written = Vec::new();
+    package.write(&mut written)?;
+    assert_eq!(signed, written);
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_edit_header() -> Result<(), Box<dyn std::error::Error>> {
+    let (signer, verifier) = test_signer_and_verifier()?;
+
+    let rpm_file = std::fs::File::open(
+        cargo_manifest_dir().join("test_assets/rpm-sign-4.15.1-1.fc31.x86_64.rpm"),
+    )?;
+    let mut package = RPMPackage::parse(&mut std::io::BufReader::new(rpm_file))?;
+    let file_entries = package.metadata.header.get_file_entries()?;
+
+    let header = &mut package.metadata.header;
+    header.set_string(IndexTag::RPMTAG_RELEASE, "2.fc31")?;
+    header.set_string(IndexTag::RPMTAG_VENDOR, "Some Vendor")?;
+    header.set_string(IndexTag::RPMTAG_DISTTAG, "fc31")?;
+    assert!(header.remove(IndexTag::RPMTAG_URL));
+    // the signatures and digests are stale until signed again
+    assert!(!package.verify_digests()?.is_ok());
+    package.sign(&signer)?;
+
+    let mut buffer = Vec::new();
+    package.write(&mut buffer)?;
+    let package = RPMPackage::parse(&mut buffer.as_slice())?;
+    let header = &package.metadata.header;
+    assert_eq!("2.fc31", header.get_release()?);
+    assert_eq!(
+        "Some Vendor",
+        header.get_entry_string_data(IndexTag::RPMTAG_VENDOR)?
+    );
+    assert_eq!(
+        "fc31",
+        header.get_entry_string_data(IndexTag::RPMTAG_DISTTAG)?
+    );
+    assert!(header.find_entry_or_err(&IndexTag::RPMTAG_URL).is_err());
+    assert_eq!(file_entries, header.get_file_entries()?);
+    assert!(package.verify_digests()?.is_ok());
+    package.verify_signature(&verifier)?;
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_sign_ed25519() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::pgp::{Signer, Verifier};
+
+    let (signing_key, verification_key) = signature::pgp::test::load_ed25519_asc_keys();
+    let signer = Signer::load_from_asc_bytes(signing_key.as_ref())?
+        .with_algorithm::<signature::algorithm::EdDSA>();
+    let verifier = Verifier::load_from_asc_bytes(verification_key.as_ref())?;
+
+    let package = test_package_builder()?.build_and_sign(&signer)?;
+
+    // rpm keeps the RSA tags for RSA signatures only
+    let signature = &package.metadata.signature;
+    for tag in &[
+        IndexSignatureTag::RPMSIGTAG_DSA,
+        IndexSignatureTag::RPMSIGTAG_GPG,
+    ] {
+        assert!(signature.find_entry_or_err(tag).is_ok());
+    }
+    for tag in &[
+        IndexSignatureTag::RPMSIGTAG_RSA,
+        IndexSignatureTag::RPMSIGTAG_PGP,
+    ] {
+        assert!(signature.find_entry_or_err(tag).is_err());
+    }
+
+    package.verify_signature(&verifier)?;
+    let report = package.verify(&verifier)?;
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(2, report.verified_key_ids().count());
+
+    let (_, rsa_verifier) = test_signer_and_verifier()?;
+    assert!(package.verify_signature(&rsa_verifier).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "signature-pgp")]
+#[test]
+fn test_reproducible_signing() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeZone;
+
+    let (signer, _) = test_signer_and_verifier()?;
+    let signer = signer
+        .with_hash_algorithm(pgp::crypto::hash::HashAlgorithm::SHA2_512)?
+        .with_creation_time(chrono::Utc.timestamp_opt(1_600_000_000, 0).unwrap());
+
+    let build = || -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let package = test_package_builder()?.build_and_sign(&signer)?;
+        let mut out = Vec::new();
+        package.write(&mut out)?;
+        Ok(out)
+    };
+    assert_eq!(build()?, build()?);
+    Ok(())
+}
+
+/// Asynchronous signer and verifier reading all data before handing it to the wrapped
+/// synchronous one, so the packages only see the async traits.
+#[cfg(all(feature = "signature-pgp", feature = "async-tokio"))]
+mod async_signing {
+    use super::*;
+    use signature::algorithm::RSA;
+    use signature::{Signing, Verifying};
+    use tokio::io::{AsyncRead, AsyncReadExt};
+
+    async fn read_all<R: AsyncRead + Unpin + Send>(mut data: R) -> Result<Vec<u8>, RPMError> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[derive(Debug)]
+    pub(super) struct AsyncSigner(pub(super) signature::pgp::Signer);
+
+    #[async_trait::async_trait]
+    impl signature::AsyncSigning<RSA> for AsyncSigner {
+        type Signature = Vec<u8>;
+
+        async fn sign<R: AsyncRead + Unpin + Send>(
+            &self,
+            data: R,
+        ) -> Result<Self::Signature, RPMError> {
+            let data = read_all(data).await?;
+            self.0.sign(data.as_slice())
+        }
+    }
+
+    #[derive(Debug)]
+    pub(super) struct AsyncVerifier(pub(super) signature::pgp::Verifier);
+
+    #[async_trait::async_trait]
+    impl signature::AsyncVerifying<RSA> for AsyncVerifier {
+        type Signature = Vec<u8>;
+
+        async fn verify<R: AsyncRead + Unpin + Send>(
+            &self,
+            data: R,
+            signature: &[u8],
+        ) -> Result<(), RPMError> {
+            let data = read_all(data).await?;
+            self.0.verify(data.as_slice(), signature)
+        }
+    }
+}
+
+#[cfg(all(feature = "signature-pgp", feature = "async-tokio"))]
+#[tokio::test]
+async fn test_signing_async() -> Result<(), Box<dyn std::error::Error>> {
+    use async_signing::{AsyncSigner, AsyncVerifier};
+    use signature::pgp::Verifier;
+
+    let (signer, verifier) = test_signer_and_verifier()?;
+    let async_signer = AsyncSigner(signer.clone());
+    let async_verifier = AsyncVerifier(verifier.clone());
+
+    let mut package = test_package_builder()?
+        .build_and_sign_async(&async_signer)
+        .await?;
+    package.verify_signature(&verifier)?;
+    package.verify_signature_async(&async_verifier).await?;
+    let report = package.verify_async(&async_verifier).await?;
+    assert!(report.is_ok(), "{:?}", report);
+
+    // re-sign the unsigned package
+    package.metadata.signature = Header::<IndexSignatureTag>::builder()
+        .add_sha256_digest("")
+        .build(0);
+    assert!(package
+        .verify_signature_async(&async_verifier)
+        .await
+        .is_err());
+    package.sign_async(&async_signer).await?;
+    package.verify_signature(&verifier)?;
+    package.verify_signature_async(&async_verifier).await?;
+    package.sign(&signer)?;
+    package.verify_signature_async(&async_verifier).await?;
+
+    // signatures by another key are rejected
+    let (_, other_key) = signature::pgp::test::load_second_asc_keys();
+    let other_verifier = AsyncVerifier(Verifier::load_from_asc_bytes(other_key.as_ref())?);
+    assert!(package
+        .verify_signature_async(&other_verifier)
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[cfg(feature = "signature-sequoia")]
+#[test]
+fn test_sign_sequoia() -> Result<(), Box<dyn std::error::Error>> {
+    use signature::sequoia::{Signer, Verifier};
+
+    let signer = Signer::load_from_asc_bytes(include_bytes!("../test_assets/secret_key.asc"))?;
+    let verifier = Verifier::load_from_asc_bytes(include_bytes!("../test_assets/public_key.asc"))?;
+
+    let package = test_package_builder()?.build_and_sign(&signer)?;
+    package.verify_signature(&verifier)?;
+
+    let mut out = Vec::new();
+    package.write(&mut out)?;
+    let package = RPMPackage::parse(&mut out.as_slice())?;
+    let report = package.verify(&verifier)?;
+    assert!(report.is_ok(), "{:?}", report);
+
+    let other =
+        Verifier::load_from_asc_bytes(include_bytes!("../test_assets/second_public_key.asc"))?;
+    assert!(package.verify_signature(&other).is_err());
+    Ok(())
+}